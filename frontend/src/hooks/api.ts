@@ -1,6 +1,6 @@
 import { useAccount, useSendMessage, useReadWasmState } from '@gear-js/react-hooks';
 import { ADDRESS } from 'consts';
-import { Params, Token } from 'types';
+import { Params, Token, TokenInfo } from 'types';
 import { useParams } from 'react-router-dom';
 import stateMetaWasm from 'assets/wasm/state.meta.wasm';
 import metaTxt from 'assets/meta/meta.txt';
@@ -18,8 +18,8 @@ function useNFTState<T>(functionName: string, payload?: any) {
 
 function useNFT() {
   const { id } = useParams() as Params;
-  const { state } = useNFTState<Token>('token', id);
-  return state;
+  const { state } = useNFTState<TokenInfo>('token', id);
+  return state?.token;
 }
 
 function useNFTs() {
@@ -14,6 +14,11 @@ type Token = {
   reference: string;
 };
 
+type TokenInfo = {
+  token: Token;
+  transferable: boolean;
+};
+
 type Attributes = {
   [key: string]: string;
 };
@@ -23,4 +28,4 @@ type TokenDetails = {
   attributes?: Attributes;
 };
 
-export type { Params, Token, Attributes, TokenDetails };
+export type { Params, Token, TokenInfo, Attributes, TokenDetails };
@@ -9,15 +9,174 @@ use gear_lib::non_fungible_token::{
 use gmeta::{In, InOut, Metadata};
 use gstd::{prelude::*, ActorId};
 
-pub use gear_lib::non_fungible_token::delegated::DelegatedApproveMessage;
-use primitive_types::H256;
+pub use primitive_types::H256;
+
+pub mod merkle;
 
 pub struct NFTMetadata;
 
+#[derive(Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, TypeInfo, Hash)]
+pub struct DelegatedApproveMessage {
+    pub token_owner_id: ActorId,
+    pub approved_actor_id: ActorId,
+    pub nft_program_id: ActorId,
+    pub token_id: TokenId,
+    pub expiration_timestamp: u64,
+    /// Must be equal to the current delegation nonce of `token_owner_id`,
+    /// which is incremented by every accepted signature.
+    pub nonce: u64,
+}
+
+/// A transfer signed by the token owner that anyone can submit on its behalf.
+#[derive(Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, TypeInfo, Hash)]
+pub struct DelegatedTransferMessage {
+    pub token_owner_id: ActorId,
+    pub to: ActorId,
+    pub nft_program_id: ActorId,
+    pub token_id: TokenId,
+    pub expiration_timestamp: u64,
+    /// Shares the delegation nonce with [`DelegatedApproveMessage::nonce`].
+    pub nonce: u64,
+}
+
+/// Permission to mint a token signed off-chain by a [`Role::Minter`] holder and
+/// redeemed with [`NFTAction::RedeemVoucher`].
+#[derive(Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, TypeInfo, Hash)]
+pub struct MintVoucher {
+    pub minter: ActorId,
+    /// Recipient of the token; `None` mints to whoever redeems the voucher.
+    pub to: Option<ActorId>,
+    pub token_metadata: TokenMetadata,
+    /// Overrides [`InitNFT::soulbound`] for the token.
+    pub transferable: Option<bool>,
+    pub attributes: Attributes,
+    /// Value that must be attached to [`NFTAction::RedeemVoucher`].
+    pub price: u128,
+    pub nft_program_id: ActorId,
+    pub expiration_timestamp: u64,
+    /// Any number the minter hasn't used in another voucher.
+    pub nonce: u64,
+}
+
+/// Token minted by [`NFTAction::BatchMint`].
+#[derive(Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, TypeInfo, Hash)]
+pub struct MintItem {
+    pub to: ActorId,
+    pub token_metadata: TokenMetadata,
+    /// Overrides [`InitNFT::soulbound`] for the token if the caller has
+    /// [`Role::Minter`].
+    pub transferable: Option<bool>,
+    /// Ignored unless the caller has [`Role::Minter`].
+    pub attributes: Attributes,
+}
+
+/// Number of blocks the recipient of [`NFTAction::SafeTransfer`] has to reply
+/// to [`NFTReceiverAction::OnNftReceived`].
+pub const SAFE_TRANSFER_TIMEOUT: u32 = 10;
+
+/// Sent to the recipient of [`NFTAction::SafeTransfer`], which must reply with
+/// `true` to accept the token.
+#[derive(Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, TypeInfo, Hash)]
+pub enum NFTReceiverAction {
+    OnNftReceived {
+        from: ActorId,
+        token_id: TokenId,
+        data: Vec<u8>,
+    },
+}
+
 #[derive(Default, Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, TypeInfo, Hash)]
 pub struct Constraints {
     pub max_mint_count: Option<u32>,
+    /// Holders of [`Role::Minter`].
     pub authorized_minters: Vec<ActorId>,
+    /// Allows any account to mint by paying the price of
+    /// [`Constraints::price_tiers`]; [`Role::Minter`] holders mint for free.
+    pub public_mint: bool,
+    /// Sorted by [`PriceTier::supply`]; no tiers make minting free.
+    pub price_tiers: Vec<PriceTier>,
+    /// Sorted and non-overlapping mint phases. If there are any, they replace
+    /// [`Constraints::public_mint`] and [`Constraints::price_tiers`] for
+    /// accounts without [`Role::Minter`], and minting outside of them is closed.
+    pub phases: Vec<MintPhase>,
+    /// Number of tokens an account without [`Role::Minter`] can mint in total.
+    /// Counts mints rather than holdings, so transfers don't reset it.
+    pub max_per_wallet: Option<u32>,
+}
+
+impl Constraints {
+    /// Returns the index and the phase active at the block `timestamp`.
+    pub fn current_phase(&self, timestamp: u64) -> Option<(u32, &MintPhase)> {
+        self.phases
+            .iter()
+            .enumerate()
+            .find(|(_index, phase)| phase.start <= timestamp && timestamp < phase.end)
+            .map(|(index, phase)| (index as u32, phase))
+    }
+}
+
+#[derive(Default, Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, TypeInfo, Hash)]
+pub struct MintPhase {
+    /// Block timestamp when the phase starts.
+    pub start: u64,
+    /// Block timestamp when the phase ends, exclusive.
+    pub end: u64,
+    /// Price of one token in value.
+    pub price: u128,
+    pub max_per_wallet: Option<u32>,
+    pub eligibility: Eligibility,
+}
+
+#[derive(Default, Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, TypeInfo, Hash)]
+pub enum Eligibility {
+    /// Only the listed accounts can mint.
+    Allowlist(Vec<ActorId>),
+    /// Only accounts with an [`AllowlistProof`] can mint.
+    MerkleAllowlist,
+    Public,
+    /// Nobody can mint, e.g. during a pause between phases.
+    #[default]
+    Closed,
+}
+
+#[derive(Default, Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, TypeInfo, Hash)]
+pub struct PriceTier {
+    /// Number of existing tokens from which the tier applies.
+    pub supply: u32,
+    /// Price of one token in value.
+    pub price: u128,
+}
+
+#[derive(Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, TypeInfo, Hash)]
+pub enum Role {
+    /// Grants and revokes roles.
+    Admin,
+    Minter,
+    /// Burns any token regardless of its owner.
+    Burner,
+    Pauser,
+    MetadataEditor,
+}
+
+#[derive(Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, TypeInfo, Hash)]
+pub enum AttributeValue {
+    String(String),
+    Number(i64),
+    Bool(bool),
+    /// Timestamp in milliseconds.
+    Date(u64),
+}
+
+/// Token attributes stored on-chain; keys are unique.
+pub type Attributes = Vec<(String, AttributeValue)>;
+
+/// Proves that the caller is allowed to mint up to `allocation` tokens in total
+/// by the allowlist set with [`NFTAction::SetMerkleRoot`].
+#[derive(Default, Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, TypeInfo, Hash)]
+pub struct AllowlistProof {
+    pub allocation: u32,
+    /// Built with [`merkle::MerkleTree::proof`] for [`merkle::leaf`].
+    pub proof: Vec<H256>,
 }
 
 #[derive(Default, Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, TypeInfo, Hash)]
@@ -25,17 +184,46 @@ pub struct InitNFT {
     pub collection: Collection,
     pub royalties: Option<Royalties>,
     pub constraints: Constraints,
+    /// Number of blocks after which a processed transaction is evicted from
+    /// the cache, making its `transaction_id` reusable. `None` keeps
+    /// transactions forever.
+    pub transaction_ttl: Option<u32>,
+    /// Makes tokens non-transferable and non-approvable unless overridden at
+    /// mint. Soulbound tokens can still be minted and burned.
+    pub soulbound: bool,
+    /// Whether token owners can update the metadata of their tokens in
+    /// addition to [`Role::MetadataEditor`] holders.
+    pub owner_can_update_metadata: bool,
 }
 
 #[derive(Default, Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, TypeInfo, Hash)]
 pub struct Collection {
     pub name: String,
     pub description: String,
+    pub symbol: String,
+    /// Prefix of token URIs, see the `token_uri` state function.
+    pub base_uri: String,
+    pub banner_url: String,
+    pub logo_url: String,
+    pub external_link: String,
+    /// Pairs of a social network name and a link.
+    pub socials: Vec<(String, String)>,
+}
+
+/// Fields of [`Collection`] changed by [`NFTAction::UpdateCollection`]; `None`
+/// keeps the current value.
+#[derive(Default, Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, TypeInfo, Hash)]
+pub struct CollectionUpdate {
+    pub description: Option<String>,
+    pub banner_url: Option<String>,
+    pub logo_url: Option<String>,
+    pub external_link: Option<String>,
+    pub socials: Option<Vec<(String, String)>>,
 }
 
 impl Metadata for NFTMetadata {
     type Init = In<InitNFT>;
-    type Handle = InOut<NFTAction, NFTEvent>;
+    type Handle = InOut<NFTAction, Result<NFTEvent, NFTError>>;
     type Reply = ();
     type Others = ();
     type Signal = ();
@@ -44,9 +232,31 @@ impl Metadata for NFTMetadata {
 
 #[derive(Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, TypeInfo, Hash)]
 pub enum NFTAction {
+    /// `transferable` overrides [`InitNFT::soulbound`] for the token. It and
+    /// `attributes` are ignored unless the caller has [`Role::Minter`], which
+    /// also applies to [`NFTAction::MintTo`] and [`NFTAction::BatchMint`].
+    ///
+    /// With `allowlist_proof`, the caller can mint without [`Role::Minter`] or
+    /// [`Constraints::public_mint`], paying the regular price.
     Mint {
         transaction_id: u64,
         token_metadata: TokenMetadata,
+        transferable: Option<bool>,
+        attributes: Attributes,
+        allowlist_proof: Option<AllowlistProof>,
+    },
+    MintTo {
+        transaction_id: u64,
+        to: ActorId,
+        token_metadata: TokenMetadata,
+        transferable: Option<bool>,
+        attributes: Attributes,
+    },
+    /// Mints all `items` or none of them if they don't fit in
+    /// [`Constraints::max_mint_count`].
+    BatchMint {
+        transaction_id: u64,
+        items: Vec<MintItem>,
     },
     Burn {
         transaction_id: u64,
@@ -57,6 +267,25 @@ pub enum NFTAction {
         to: ActorId,
         token_id: TokenId,
     },
+    /// Transfers the token and, if `require_callback` is set, reverts the
+    /// transfer unless `to` accepts it in reply to
+    /// [`NFTReceiverAction::OnNftReceived`] within [`SAFE_TRANSFER_TIMEOUT`]
+    /// blocks.
+    ///
+    /// Gear doesn't let a program tell programs from user accounts, so the
+    /// caller unsets `require_callback` for user accounts, which can't reply.
+    SafeTransfer {
+        transaction_id: u64,
+        to: ActorId,
+        token_id: TokenId,
+        data: Vec<u8>,
+        require_callback: bool,
+    },
+    /// Transfers all tokens or none of them if any transfer isn't allowed.
+    BatchTransfer {
+        transaction_id: u64,
+        transfers: Vec<(ActorId, TokenId)>,
+    },
     TransferPayout {
         transaction_id: u64,
         to: ActorId,
@@ -71,12 +300,28 @@ pub enum NFTAction {
         transaction_id: u64,
         to: ActorId,
         token_id: TokenId,
+        /// Block timestamp after which the approval stops working.
+        expires_at: Option<u64>,
+    },
+    RevokeApproval {
+        transaction_id: u64,
+        to: ActorId,
+        token_id: TokenId,
+    },
+    ClearApprovals {
+        transaction_id: u64,
+        token_id: TokenId,
     },
     DelegatedApprove {
         transaction_id: u64,
         message: DelegatedApproveMessage,
         signature: [u8; 64],
     },
+    DelegatedTransfer {
+        transaction_id: u64,
+        message: DelegatedTransferMessage,
+        signature: [u8; 64],
+    },
     Owner {
         token_id: TokenId,
     },
@@ -84,6 +329,35 @@ pub enum NFTAction {
         to: ActorId,
         token_id: TokenId,
     },
+    /// Allows or disallows `operator` to transfer and burn all tokens of the
+    /// caller.
+    SetApprovalForAll {
+        operator: ActorId,
+        approved: bool,
+    },
+    IsApprovedForAll {
+        owner: ActorId,
+        operator: ActorId,
+    },
+    /// Invalidates all outstanding signatures of the caller.
+    IncrementNonce,
+    /// Forbids transferring and burning the token until the block timestamp
+    /// `until` or forever if it's `None`. Callable by the token owner and
+    /// accounts approved to transfer the token, e.g. a staking program, but
+    /// only the owner and its operators can lock the token forever.
+    LockToken {
+        token_id: TokenId,
+        until: Option<u64>,
+    },
+    /// Removes the lock set by the caller. The token owner can also remove a
+    /// lock set by an account it no longer approves, e.g. after
+    /// [`NFTAction::RevokeApproval`].
+    UnlockToken {
+        token_id: TokenId,
+    },
+    IsLocked {
+        token_id: TokenId,
+    },
     Clear {
         transaction_hash: H256,
     },
@@ -91,14 +365,103 @@ pub enum NFTAction {
         transaction_id: u64,
         minter_id: ActorId,
     },
+    RemoveMinter {
+        transaction_id: u64,
+        minter_id: ActorId,
+    },
+    /// Replaces all holders of [`Role::Minter`] with `minters`.
+    SetMinters {
+        transaction_id: u64,
+        minters: Vec<ActorId>,
+    },
+    IsTransactionCached {
+        transaction_hash: H256,
+    },
+    GrantRole {
+        role: Role,
+        account: ActorId,
+    },
+    RevokeRole {
+        role: Role,
+        account: ActorId,
+    },
+    RenounceRole {
+        role: Role,
+    },
+    /// Proposes `new_owner` as the collection owner; the transfer completes
+    /// once `new_owner` sends [`NFTAction::AcceptOwnership`].
+    TransferOwnership {
+        new_owner: ActorId,
+    },
+    /// Moves [`Role::Admin`] from the previous owner to the caller along with
+    /// the ownership. Admins granted by the owner keep the role.
+    AcceptOwnership,
+    /// Drops [`Role::Admin`] of the owner along with the ownership.
+    RenounceOwnership,
+    /// Rejects all token mutations until [`NFTAction::Unpause`].
+    Pause,
+    Unpause,
+    /// Replaces the token metadata unless it's frozen.
+    UpdateMetadata {
+        transaction_id: u64,
+        token_id: TokenId,
+        metadata: TokenMetadata,
+    },
+    /// Replaces all attributes of the token. Follows the same rules as
+    /// [`NFTAction::UpdateMetadata`].
+    UpdateAttributes {
+        transaction_id: u64,
+        token_id: TokenId,
+        attributes: Attributes,
+    },
+    /// Makes the token metadata and attributes permanently immutable.
+    FreezeMetadata {
+        token_id: TokenId,
+    },
+    /// Makes the metadata of all tokens permanently immutable. Callable by the
+    /// collection owner only.
+    FreezeAllMetadata,
+    SetBaseUri {
+        base_uri: String,
+    },
+    UpdateCollection(CollectionUpdate),
+    /// Sends the proceeds of public mints to the collection owner.
+    Withdraw,
+    /// Sends the caller the attached value that couldn't be refunded to them
+    /// earlier, together with the value attached to this message.
+    ClaimRefund,
+    /// Replaces the phases that haven't started yet with `phases`. Callable by
+    /// the collection owner only.
+    SetUpcomingPhases {
+        phases: Vec<MintPhase>,
+    },
+    /// Sets the root of the allowlist built with [`merkle::MerkleTree`] or
+    /// disables the allowlist. Callable by the collection owner only.
+    SetMerkleRoot {
+        root: Option<H256>,
+    },
+    /// Mints the token described by `voucher`, which can be redeemed only once
+    /// by anyone attaching its price. Mint phases don't apply, but the
+    /// redemption counts towards [`Constraints::max_per_wallet`] of the caller.
+    RedeemVoucher {
+        voucher: MintVoucher,
+        signature: [u8; 64],
+    },
 }
 
 #[derive(Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, TypeInfo, Hash)]
 pub enum NFTEvent {
     Transfer(NFTTransfer),
+    BatchMint(Vec<NFTTransfer>),
+    BatchTransfer(Vec<NFTTransfer>),
     TransferPayout(NFTTransferPayout),
     NFTPayout(Payout),
     Approval(NFTApproval),
+    ApprovalRevoked(NFTApproval),
+    ApprovalsCleared {
+        owner: ActorId,
+        token_id: TokenId,
+    },
     Owner {
         owner: ActorId,
         token_id: TokenId,
@@ -108,9 +471,180 @@ pub enum NFTEvent {
         token_id: TokenId,
         approved: bool,
     },
+    ApprovalForAll {
+        owner: ActorId,
+        operator: ActorId,
+        approved: bool,
+    },
+    IsApprovedForAll {
+        owner: ActorId,
+        operator: ActorId,
+        approved: bool,
+    },
+    NonceIncremented {
+        owner: ActorId,
+        nonce: u64,
+    },
+    TokenLocked {
+        token_id: TokenId,
+        locker: ActorId,
+        until: Option<u64>,
+    },
+    TokenUnlocked {
+        token_id: TokenId,
+    },
+    IsLocked {
+        token_id: TokenId,
+        locked: bool,
+    },
     MinterAdded {
         minter_id: ActorId,
     },
+    MinterRemoved {
+        minter_id: ActorId,
+    },
+    MintersSet {
+        minters: Vec<ActorId>,
+    },
+    Cleared {
+        transaction_hash: H256,
+    },
+    IsTransactionCached {
+        transaction_hash: H256,
+        cached: bool,
+    },
+    RoleGranted {
+        role: Role,
+        account: ActorId,
+    },
+    RoleRevoked {
+        role: Role,
+        account: ActorId,
+    },
+    OwnershipTransferStarted {
+        previous_owner: ActorId,
+        new_owner: ActorId,
+    },
+    OwnershipTransferred {
+        previous_owner: ActorId,
+        new_owner: ActorId,
+    },
+    Paused,
+    Unpaused,
+    MetadataUpdated {
+        token_id: TokenId,
+        metadata: TokenMetadata,
+    },
+    AttributesUpdated {
+        token_id: TokenId,
+        attributes: Attributes,
+    },
+    MetadataFrozen {
+        token_id: TokenId,
+    },
+    AllMetadataFrozen,
+    BaseUriSet {
+        base_uri: String,
+    },
+    CollectionUpdated(Collection),
+    Withdrawn {
+        amount: u128,
+    },
+    RefundClaimed {
+        amount: u128,
+    },
+    UpcomingPhasesSet {
+        phases: Vec<MintPhase>,
+    },
+    MerkleRootSet {
+        root: Option<H256>,
+    },
+}
+
+#[derive(Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, TypeInfo, Hash)]
+pub enum NFTError {
+    /// Minting would exceed [`Constraints::max_mint_count`].
+    MaxMintCountExceeded,
+    /// The caller or the signer of a [`MintVoucher`] doesn't have [`Role::Minter`].
+    NotAuthorizedMinter,
+    TokenDoesNotExist,
+    /// The caller isn't the owner of the token.
+    NotOwner,
+    /// The caller is neither the owner of the token nor approved for it or all
+    /// tokens of the owner.
+    NotOwnerOrApproved,
+    /// The caller is neither the owner of the token nor an operator for all
+    /// tokens of the owner.
+    NotOwnerOrOperator,
+    ZeroAddress,
+    /// The approval would expire at or before the current block timestamp.
+    ApprovalExpired,
+    /// Only the program itself can clear cached transactions.
+    NotAllowedToClear,
+    /// The signer of a delegated message doesn't own the token.
+    WrongTokenOwner,
+    /// The caller isn't [`DelegatedApproveMessage::approved_actor_id`].
+    NotApprovedActor,
+    /// The signed message is addressed to another program.
+    WrongProgramId,
+    /// The signed message or voucher has expired.
+    DelegationExpired,
+    /// The signed nonce isn't the current delegation nonce of the signer.
+    InvalidNonce,
+    InvalidSignature,
+    /// The caller doesn't have the role required for the action.
+    MissingRole(Role),
+    /// The caller isn't the collection owner.
+    NotCollectionOwner,
+    /// The caller isn't the pending owner set by [`NFTAction::TransferOwnership`].
+    NotPendingOwner,
+    /// The collection is paused by [`NFTAction::Pause`].
+    Paused,
+    /// The same token is listed more than once in [`NFTAction::BatchTransfer`].
+    DuplicateToken,
+    /// The recipient of [`NFTAction::SafeTransfer`] rejected the token or
+    /// didn't reply in time.
+    TransferRejected,
+    /// The token is awaiting the recipient's reply to
+    /// [`NFTReceiverAction::OnNftReceived`].
+    TokenPending,
+    /// A [`NFTAction::SafeTransfer`] with the same transaction id is awaiting
+    /// the recipient's reply.
+    TransactionPending,
+    /// The token is soulbound.
+    NonTransferable,
+    /// The token is locked by [`NFTAction::LockToken`].
+    TokenLocked,
+    /// The token isn't locked by the caller, or its locker is still approved
+    /// for the token.
+    NotLocker,
+    /// The token metadata is frozen.
+    MetadataFrozen,
+    /// The attached value is less than the mint price.
+    InsufficientValue,
+    /// The proceeds or the refund couldn't be sent, e.g. since they're below
+    /// the existential deposit.
+    WithdrawalFailed,
+    /// No mint phase is active or the current one is closed.
+    MintClosed,
+    /// The caller isn't eligible for the current mint phase.
+    NotEligible,
+    /// Minting would exceed the per-wallet limit or the allowlist allocation.
+    MintLimitReached,
+    /// Phases must be sorted and not overlap, and upcoming ones must start in
+    /// the future.
+    InvalidPhases,
+    /// The allowlist proof doesn't match the Merkle root or no root is set.
+    InvalidProof,
+    /// The [`MintVoucher`] is already redeemed.
+    VoucherUsed,
+}
+
+#[derive(Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, TypeInfo, Hash)]
+pub struct TokenLock {
+    pub locker: ActorId,
+    /// Block timestamp when the lock expires.
+    pub until: Option<u64>,
 }
 
 #[derive(Default, Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, TypeInfo, Hash)]
@@ -128,9 +662,35 @@ pub struct IoNFTState {
 #[derive(Default, Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, TypeInfo, Hash)]
 pub struct IoNFT {
     pub token: IoNFTState,
+    pub collection: Collection,
+    pub proceeds: u128,
+    /// Attached value that couldn't be returned to its sender, see
+    /// [`NFTAction::ClaimRefund`].
+    pub refunds: Vec<(ActorId, u128)>,
+    pub constraints: Constraints,
+    /// Tokens minted by an account in a phase, keyed by the phase index.
+    pub phase_mints: Vec<((u32, ActorId), u32)>,
+    pub merkle_root: Option<H256>,
+    /// Tokens minted by an account with [`AllowlistProof`]s.
+    pub allowlist_claims: Vec<(ActorId, u32)>,
+    /// Tokens minted by an account with any mint action.
+    pub minted_by: Vec<(ActorId, u32)>,
+    /// Hashes of the minter and nonce of redeemed [`MintVoucher`]s.
+    pub used_vouchers: Vec<H256>,
     pub token_id: TokenId,
     pub owner: ActorId,
+    pub pending_owner: Option<ActorId>,
     pub transactions: Vec<(H256, NFTEvent)>,
+    pub roles: Vec<(Role, Vec<ActorId>)>,
+    pub paused: bool,
+    pub operators: Vec<(ActorId, Vec<ActorId>)>,
+    pub approval_expirations: Vec<(TokenId, Vec<(ActorId, u64)>)>,
+    pub delegation_nonces: Vec<(ActorId, u64)>,
+    pub non_transferable: Vec<TokenId>,
+    pub locks: Vec<(TokenId, TokenLock)>,
+    pub frozen_metadata: Vec<TokenId>,
+    pub all_metadata_frozen: bool,
+    pub attributes: Vec<(TokenId, Attributes)>,
 }
 
 impl From<&NFTState> for IoNFTState {
@@ -186,15 +746,33 @@ pub struct Nft {
     pub description: String,
     pub media_url: String,
     pub attrib_url: String,
+    pub transferable: bool,
+    pub attributes: Attributes,
+}
+
+/// Output of the `token` state function: a [`Token`] with the flags it can't
+/// carry.
+#[derive(Default, Debug, Encode, Decode, Clone, TypeInfo)]
+pub struct TokenInfo {
+    pub token: Token,
+    pub transferable: bool,
 }
 
 #[derive(Default, Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, TypeInfo, Hash)]
 pub struct State {
     pub tokens: Vec<(TokenId, Nft)>,
     pub owner: ActorId,
+    pub pending_owner: Option<ActorId>,
     pub transactions: Vec<(H256, NFTEvent)>,
     pub owners: Vec<(ActorId, TokenId)>,
     pub collection: Collection,
     pub nonce: TokenId,
     pub constraints: Constraints,
+    pub paused: bool,
+}
+
+pub fn get_hash(account: &ActorId, transaction_id: u64) -> H256 {
+    let account: [u8; 32] = (*account).into();
+    let transaction_id = transaction_id.to_be_bytes();
+    sp_core_hashing::blake2_256(&[account.as_slice(), transaction_id.as_slice()].concat()).into()
 }
@@ -0,0 +1,81 @@
+//! Merkle trees for allowlists, shared by the contract and off-chain tools.
+//!
+//! Leaves are [`leaf`] hashes and pairs of nodes are hashed in sorted order, so
+//! proofs don't need to carry the position of every sibling.
+
+use crate::{get_hash, H256};
+use gstd::{prelude::*, ActorId};
+
+/// Returns the leaf allowing `account` to mint up to `allocation` tokens.
+pub fn leaf(account: &ActorId, allocation: u32) -> H256 {
+    get_hash(account, allocation.into())
+}
+
+fn hash_pair(a: &H256, b: &H256) -> H256 {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    sp_core_hashing::blake2_256(&[first.as_bytes(), second.as_bytes()].concat()).into()
+}
+
+/// Checks that `leaf` belongs to the tree with `root`.
+pub fn verify(root: &H256, leaf: H256, proof: &[H256]) -> bool {
+    proof
+        .iter()
+        .fold(leaf, |node, sibling| hash_pair(&node, sibling))
+        .eq(root)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleTree {
+    /// Layers from the leaves to the root. A node without a sibling is
+    /// promoted to the next layer as is.
+    layers: Vec<Vec<H256>>,
+}
+
+impl MerkleTree {
+    pub fn new(leaves: Vec<H256>) -> Self {
+        let mut layers = vec![leaves];
+
+        while layers.last().map_or(false, |layer| layer.len() > 1) {
+            let next = layers
+                .last()
+                .expect("Layers can't be empty")
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => hash_pair(a, b),
+                    [a] => *a,
+                    _ => unreachable!("Chunks contain one or two nodes"),
+                })
+                .collect();
+            layers.push(next);
+        }
+
+        Self { layers }
+    }
+
+    /// Returns the root or the zero hash for a tree without leaves.
+    pub fn root(&self) -> H256 {
+        self.layers
+            .last()
+            .and_then(|layer| layer.first())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns the proof for the leaf at `index` or `None` if it's out of
+    /// bounds.
+    pub fn proof(&self, mut index: usize) -> Option<Vec<H256>> {
+        if index >= self.layers[0].len() {
+            return None;
+        }
+
+        let mut proof = Vec::new();
+        for layer in &self.layers[..self.layers.len() - 1] {
+            if let Some(sibling) = layer.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+
+        Some(proof)
+    }
+}
@@ -1,12 +1,25 @@
-use gear_lib::non_fungible_token::{io::NFTTransfer, nft_core::*, state::*, token::*};
+use core::mem;
+use gear_lib::non_fungible_token::{
+    io::{NFTApproval, NFTTransfer, NFTTransferPayout},
+    nft_core::*,
+    state::*,
+    token::*,
+};
 use gear_lib_derive::{NFTCore, NFTMetaState, NFTStateKeeper};
 use gmeta::Metadata;
 use gstd::{errors::Result as GstdResult, exec, msg, prelude::*, ActorId, MessageId};
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 use nft_io::{
-    Collection, Constraints, InitNFT, IoNFT, NFTAction, NFTEvent, NFTMetadata, Nft, State,
+    get_hash, merkle, AllowlistProof, AttributeValue, Attributes, Collection, CollectionUpdate,
+    Constraints, DelegatedApproveMessage, DelegatedTransferMessage, Eligibility, InitNFT, IoNFT,
+    IoNFTState, MintPhase, MintVoucher, NFTAction, NFTError, NFTEvent, NFTMetadata,
+    NFTReceiverAction, Nft, Role, State, TokenLock, H256, SAFE_TRANSFER_TIMEOUT,
 };
-use primitive_types::{H256, U256};
+use primitive_types::U256;
+use schnorrkel::{PublicKey, Signature};
+
+/// Signing context used by `sp_core::sr25519` and Substrate-based wallets.
+const SIGNING_CONTEXT: &[u8] = b"substrate";
 
 #[derive(Debug, Default, NFTStateKeeper, NFTCore, NFTMetaState)]
 pub struct Contract {
@@ -14,9 +27,48 @@ pub struct Contract {
     pub token: NFTState,
     pub token_id: TokenId,
     pub owner: ActorId,
+    pub pending_owner: Option<ActorId>,
     pub transactions: HashMap<H256, NFTEvent>,
     pub collection: Collection,
     pub constraints: Constraints,
+    pub transaction_ttl: Option<u32>,
+    pub roles: HashMap<Role, HashSet<ActorId>>,
+    pub paused: bool,
+    pub operators: HashMap<ActorId, HashSet<ActorId>>,
+    pub approval_expirations: HashMap<TokenId, HashMap<ActorId, u64>>,
+    pub delegation_nonces: HashMap<ActorId, u64>,
+    /// Tokens awaiting the recipient's reply to [`NFTAction::SafeTransfer`].
+    pub pending_tokens: HashSet<TokenId>,
+    /// Hashes of [`NFTAction::SafeTransfer`] transactions awaiting the
+    /// recipient's reply.
+    pub pending_transactions: HashSet<H256>,
+    /// Default for tokens minted without an explicit `transferable` flag.
+    pub soulbound: bool,
+    pub non_transferable: HashSet<TokenId>,
+    pub locks: HashMap<TokenId, TokenLock>,
+    pub owner_can_update_metadata: bool,
+    pub frozen_metadata: HashSet<TokenId>,
+    pub all_metadata_frozen: bool,
+    pub attributes: HashMap<TokenId, BTreeMap<String, AttributeValue>>,
+    /// Value collected from public mints and not withdrawn yet.
+    pub proceeds: u128,
+    /// Attached value that couldn't be returned to its sender.
+    pub refunds: HashMap<ActorId, u128>,
+    pub phase_mints: HashMap<(u32, ActorId), u32>,
+    pub merkle_root: Option<H256>,
+    pub allowlist_claims: HashMap<ActorId, u32>,
+    pub minted_by: HashMap<ActorId, u32>,
+    pub used_vouchers: HashSet<H256>,
+}
+
+/// Outcome of [`Contract::check_constraints`].
+#[derive(Default)]
+struct MintPlan {
+    price: u128,
+    /// Index of the mint phase the tokens are minted in.
+    phase: Option<u32>,
+    /// Whether the tokens are counted in the allowlist allocation.
+    allowlisted: bool,
 }
 
 static mut CONTRACT: Option<Contract> = None;
@@ -27,214 +79,1411 @@ unsafe extern "C" fn init() {
     if config.royalties.is_some() {
         config.royalties.as_ref().expect("Unable to g").validate();
     }
+    let mut constraints = config.constraints;
+    check_phases(&constraints.phases).expect("Invalid mint phases");
+    let minters = mem::take(&mut constraints.authorized_minters);
+    let roles = HashMap::from([
+        (Role::Admin, HashSet::from([msg::source()])),
+        (Role::Minter, minters.into_iter().collect()),
+    ]);
     let nft = Contract {
         token: NFTState {
             name: config.collection.name.clone(),
+            symbol: config.collection.symbol.clone(),
+            base_uri: config.collection.base_uri.clone(),
             royalties: config.royalties,
             ..Default::default()
         },
         collection: config.collection,
-        constraints: config.constraints,
+        constraints,
+        transaction_ttl: config.transaction_ttl,
+        soulbound: config.soulbound,
+        owner_can_update_metadata: config.owner_can_update_metadata,
+        roles,
         owner: msg::source(),
         ..Default::default()
     };
     CONTRACT = Some(nft);
 }
 
-#[no_mangle]
-unsafe extern "C" fn handle() {
+#[gstd::async_main]
+async fn main() {
     let action: NFTAction = msg::load().expect("Could not load NFTAction");
-    let nft = CONTRACT.get_or_insert(Default::default());
-    match action {
+    let nft = unsafe { CONTRACT.get_or_insert(Default::default()) };
+    let value = msg::value();
+    let mut charged = 0;
+    let result = match action {
         NFTAction::Mint {
             transaction_id,
             token_metadata,
-        } => {
-            nft.check_constraints();
-            msg::reply(
-                nft.process_transaction(transaction_id, |nft| {
-                    NFTEvent::Transfer(MyNFTCore::mint(nft, token_metadata))
-                }),
-                0,
-            )
-            .expect("Error during replying with `NFTEvent::Transfer`");
-        }
+            transferable,
+            attributes,
+            allowlist_proof,
+        } => nft.process_transaction(transaction_id, |nft| {
+            nft.check_not_paused()?;
+            charged = nft.prepare_mint(1, allowlist_proof.as_ref())?;
+            let (transferable, attributes) = nft.minter_overrides(transferable, attributes);
+            Ok(NFTEvent::Transfer(MyNFTCore::mint(
+                nft,
+                &msg::source(),
+                token_metadata,
+                transferable,
+                attributes,
+            )))
+        }),
+        NFTAction::MintTo {
+            transaction_id,
+            to,
+            token_metadata,
+            transferable,
+            attributes,
+        } => nft.process_transaction(transaction_id, |nft| {
+            nft.check_not_paused()?;
+            check_zero_address(&to)?;
+            charged = nft.prepare_mint(1, None)?;
+            let (transferable, attributes) = nft.minter_overrides(transferable, attributes);
+            Ok(NFTEvent::Transfer(MyNFTCore::mint(
+                nft,
+                &to,
+                token_metadata,
+                transferable,
+                attributes,
+            )))
+        }),
+        NFTAction::BatchMint {
+            transaction_id,
+            items,
+        } => nft.process_transaction(transaction_id, |nft| {
+            nft.check_not_paused()?;
+            for item in &items {
+                check_zero_address(&item.to)?;
+            }
+            charged = nft.prepare_mint(items.len() as u32, None)?;
+            let transfers = items
+                .into_iter()
+                .map(|item| {
+                    let (transferable, attributes) =
+                        nft.minter_overrides(item.transferable, item.attributes);
+                    MyNFTCore::mint(nft, &item.to, item.token_metadata, transferable, attributes)
+                })
+                .collect();
+            Ok(NFTEvent::BatchMint(transfers))
+        }),
         NFTAction::Burn {
             transaction_id,
             token_id,
-        } => {
-            msg::reply(
-                nft.process_transaction(transaction_id, |nft| {
-                    NFTEvent::Transfer(NFTCore::burn(nft, token_id))
-                }),
-                0,
-            )
-            .expect("Error during replying with `NFTEvent::Transfer`");
-        }
+        } => nft.process_transaction(transaction_id, |nft| {
+            nft.check_not_paused()?;
+            nft.check_can_burn(token_id)?;
+            Ok(NFTEvent::Transfer(MyNFTCore::burn(nft, token_id)))
+        }),
         NFTAction::Transfer {
             transaction_id,
             to,
             token_id,
+        } => nft.process_transaction(transaction_id, |nft| {
+            nft.check_not_paused()?;
+            nft.prune_expired_approvals(token_id);
+            nft.check_can_transfer(&to, token_id)?;
+            Ok(NFTEvent::Transfer(MyNFTCore::transfer(nft, &to, token_id)))
+        }),
+        NFTAction::BatchTransfer {
+            transaction_id,
+            transfers,
+        } => nft.process_transaction(transaction_id, |nft| {
+            nft.check_not_paused()?;
+            let mut token_ids = HashSet::new();
+            for (to, token_id) in &transfers {
+                if !token_ids.insert(*token_id) {
+                    return Err(NFTError::DuplicateToken);
+                }
+                nft.prune_expired_approvals(*token_id);
+                nft.check_can_transfer(to, *token_id)?;
+            }
+            let transfers = transfers
+                .into_iter()
+                .map(|(to, token_id)| MyNFTCore::transfer(nft, &to, token_id))
+                .collect();
+            Ok(NFTEvent::BatchTransfer(transfers))
+        }),
+        NFTAction::SafeTransfer {
+            transaction_id,
+            to,
+            token_id,
+            data,
+            require_callback,
         } => {
-            msg::reply(
-                nft.process_transaction(transaction_id, |nft| {
-                    NFTEvent::Transfer(NFTCore::transfer(nft, &to, token_id))
-                }),
-                0,
-            )
-            .expect("Error during replying with `NFTEvent::Transfer`");
+            nft.safe_transfer(transaction_id, to, token_id, data, require_callback)
+                .await
         }
         NFTAction::TransferPayout {
             transaction_id,
             to,
             token_id,
             amount,
-        } => {
-            msg::reply(
-                nft.process_transaction(transaction_id, |nft| {
-                    NFTEvent::TransferPayout(NFTCore::transfer_payout(nft, &to, token_id, amount))
-                }),
-                0,
-            )
-            .expect("Error during replying with `NFTEvent::TransferPayout`");
-        }
-        NFTAction::NFTPayout { owner, amount } => {
-            msg::reply(
-                NFTEvent::NFTPayout(NFTCore::nft_payout(nft, &owner, amount)),
-                0,
-            )
-            .expect("Error during replying with `NFTEvent::NFTPayout`");
-        }
+        } => nft.process_transaction(transaction_id, |nft| {
+            nft.check_not_paused()?;
+            nft.prune_expired_approvals(token_id);
+            nft.check_can_transfer(&to, token_id)?;
+            Ok(NFTEvent::TransferPayout(MyNFTCore::transfer_payout(
+                nft, &to, token_id, amount,
+            )))
+        }),
+        NFTAction::NFTPayout { owner, amount } => Ok(NFTEvent::NFTPayout(NFTCore::nft_payout(
+            nft, &owner, amount,
+        ))),
         NFTAction::Approve {
             transaction_id,
             to,
             token_id,
-        } => {
-            msg::reply(
-                nft.process_transaction(transaction_id, |nft| {
-                    NFTEvent::Approval(NFTCore::approve(nft, &to, token_id))
-                }),
-                0,
-            )
-            .expect("Error during replying with `NFTEvent::Approval`");
-        }
-        NFTAction::Owner { token_id } => {
-            msg::reply(
-                NFTEvent::Owner {
-                    owner: NFTCore::owner_of(nft, token_id),
-                    token_id,
-                },
-                0,
-            )
-            .expect("Error during replying with `NFTEvent::Owner`");
-        }
-        NFTAction::IsApproved { to, token_id } => {
-            msg::reply(
-                NFTEvent::IsApproved {
-                    to,
-                    token_id,
-                    approved: NFTCore::is_approved_to(nft, &to, token_id),
-                },
-                0,
-            )
-            .expect("Error during replying with `NFTEvent::IsApproved`");
-        }
+            expires_at,
+        } => nft.process_transaction(transaction_id, |nft| {
+            nft.check_not_paused()?;
+            nft.check_owner(token_id)?;
+            nft.check_transferable(token_id)?;
+            check_zero_address(&to)?;
+            check_expiration(expires_at)?;
+            Ok(NFTEvent::Approval(MyNFTCore::approve(
+                nft, &to, token_id, expires_at,
+            )))
+        }),
+        NFTAction::RevokeApproval {
+            transaction_id,
+            to,
+            token_id,
+        } => nft.process_transaction(transaction_id, |nft| {
+            let owner = nft.check_owner(token_id)?;
+            if let Some(approvals) = nft.token.token_approvals.get_mut(&token_id) {
+                approvals.remove(&to);
+            }
+            if let Some(expirations) = nft.approval_expirations.get_mut(&token_id) {
+                expirations.remove(&to);
+            }
+            Ok(NFTEvent::ApprovalRevoked(NFTApproval {
+                owner,
+                approved_account: to,
+                token_id,
+            }))
+        }),
+        NFTAction::ClearApprovals {
+            transaction_id,
+            token_id,
+        } => nft.process_transaction(transaction_id, |nft| {
+            let owner = nft.check_owner(token_id)?;
+            nft.token.token_approvals.remove(&token_id);
+            nft.approval_expirations.remove(&token_id);
+            Ok(NFTEvent::ApprovalsCleared { owner, token_id })
+        }),
+        NFTAction::Owner { token_id } => Ok(NFTEvent::Owner {
+            owner: NFTCore::owner_of(nft, token_id),
+            token_id,
+        }),
+        NFTAction::IsApproved { to, token_id } => nft.token_owner(token_id).map(|_| {
+            nft.prune_expired_approvals(token_id);
+            NFTEvent::IsApproved {
+                to,
+                token_id,
+                approved: nft.is_approved(&to, token_id),
+            }
+        }),
         NFTAction::DelegatedApprove {
             transaction_id,
             message,
             signature,
-        } => {
-            msg::reply(
-                nft.process_transaction(transaction_id, |nft| {
-                    NFTEvent::Approval(NFTCore::delegated_approve(nft, message, signature))
-                }),
-                0,
-            )
-            .expect("Error during replying with `NFTEvent::Approval`");
-        }
+        } => nft.process_transaction(transaction_id, |nft| {
+            nft.check_not_paused()?;
+            nft.check_delegated_approve(&message, &signature)?;
+            nft.increment_nonce(message.token_owner_id);
+            Ok(NFTEvent::Approval(MyNFTCore::approve(
+                nft,
+                &message.approved_actor_id,
+                message.token_id,
+                None,
+            )))
+        }),
+        NFTAction::DelegatedTransfer {
+            transaction_id,
+            message,
+            signature,
+        } => nft.process_transaction(transaction_id, |nft| {
+            nft.check_not_paused()?;
+            nft.check_delegated_transfer(&message, &signature)?;
+            nft.increment_nonce(message.token_owner_id);
+            Ok(NFTEvent::Transfer(MyNFTCore::transfer(
+                nft,
+                &message.to,
+                message.token_id,
+            )))
+        }),
         NFTAction::Clear { transaction_hash } => nft.clear(transaction_hash),
+        NFTAction::IsTransactionCached { transaction_hash } => Ok(NFTEvent::IsTransactionCached {
+            transaction_hash,
+            cached: nft.transactions.contains_key(&transaction_hash),
+        }),
         NFTAction::AddMinter {
             transaction_id,
             minter_id,
-        } => {
-            nft.check_constraints();
-            msg::reply(
-                nft.process_transaction(transaction_id, |nft| {
-                    nft.constraints.authorized_minters.push(minter_id);
-                    NFTEvent::MinterAdded { minter_id }
-                }),
-                0,
-            )
-            .expect("Error during replying with `NFTEvent::Approval`");
+        } => nft.process_transaction(transaction_id, |nft| {
+            nft.check_role(Role::Admin)?;
+            nft.grant_role(Role::Minter, minter_id);
+            Ok(NFTEvent::MinterAdded { minter_id })
+        }),
+        NFTAction::RemoveMinter {
+            transaction_id,
+            minter_id,
+        } => nft.process_transaction(transaction_id, |nft| {
+            nft.check_role(Role::Admin)?;
+            nft.revoke_role(Role::Minter, &minter_id);
+            Ok(NFTEvent::MinterRemoved { minter_id })
+        }),
+        NFTAction::SetMinters {
+            transaction_id,
+            minters,
+        } => nft.process_transaction(transaction_id, |nft| {
+            nft.check_role(Role::Admin)?;
+            nft.roles
+                .insert(Role::Minter, minters.iter().copied().collect());
+            Ok(NFTEvent::MintersSet { minters })
+        }),
+        NFTAction::GrantRole { role, account } => nft.check_role(Role::Admin).map(|_| {
+            nft.grant_role(role, account);
+            NFTEvent::RoleGranted { role, account }
+        }),
+        NFTAction::RevokeRole { role, account } => nft.check_role(Role::Admin).map(|_| {
+            nft.revoke_role(role, &account);
+            NFTEvent::RoleRevoked { role, account }
+        }),
+        NFTAction::RenounceRole { role } => {
+            let account = msg::source();
+            nft.revoke_role(role, &account);
+            Ok(NFTEvent::RoleRevoked { role, account })
         }
+        NFTAction::TransferOwnership { new_owner } => nft.transfer_ownership(new_owner),
+        NFTAction::AcceptOwnership => nft.accept_ownership(),
+        NFTAction::RenounceOwnership => nft.renounce_ownership(),
+        NFTAction::SetApprovalForAll { operator, approved } => {
+            nft.set_approval_for_all(operator, approved)
+        }
+        NFTAction::IsApprovedForAll { owner, operator } => Ok(NFTEvent::IsApprovedForAll {
+            owner,
+            operator,
+            approved: nft.is_operator(&owner, &operator),
+        }),
+        NFTAction::IncrementNonce => {
+            let owner = msg::source();
+            Ok(NFTEvent::NonceIncremented {
+                owner,
+                nonce: nft.increment_nonce(owner),
+            })
+        }
+        NFTAction::LockToken { token_id, until } => nft.lock_token(token_id, until),
+        NFTAction::UnlockToken { token_id } => nft.unlock_token(token_id),
+        NFTAction::IsLocked { token_id } => nft.token_owner(token_id).map(|_| NFTEvent::IsLocked {
+            token_id,
+            locked: nft.is_locked(token_id),
+        }),
+        NFTAction::Pause => nft.set_paused(true),
+        NFTAction::Unpause => nft.set_paused(false),
+        NFTAction::UpdateMetadata {
+            transaction_id,
+            token_id,
+            metadata,
+        } => nft.process_transaction(transaction_id, |nft| {
+            nft.check_not_paused()?;
+            nft.check_metadata_editor(token_id)?;
+            nft.token
+                .token_metadata_by_id
+                .insert(token_id, Some(metadata.clone()));
+            Ok(NFTEvent::MetadataUpdated { token_id, metadata })
+        }),
+        NFTAction::UpdateAttributes {
+            transaction_id,
+            token_id,
+            attributes,
+        } => nft.process_transaction(transaction_id, |nft| {
+            nft.check_not_paused()?;
+            nft.check_metadata_editor(token_id)?;
+            nft.set_attributes(token_id, attributes);
+            Ok(NFTEvent::AttributesUpdated {
+                token_id,
+                attributes: nft.token_attributes(token_id),
+            })
+        }),
+        NFTAction::FreezeMetadata { token_id } => nft.check_metadata_editor(token_id).map(|_| {
+            nft.frozen_metadata.insert(token_id);
+            NFTEvent::MetadataFrozen { token_id }
+        }),
+        NFTAction::FreezeAllMetadata => nft.check_collection_owner().map(|_| {
+            nft.all_metadata_frozen = true;
+            NFTEvent::AllMetadataFrozen
+        }),
+        NFTAction::SetBaseUri { base_uri } => nft.check_collection_owner().map(|_| {
+            nft.token.base_uri = base_uri.clone();
+            nft.collection.base_uri = base_uri.clone();
+            NFTEvent::BaseUriSet { base_uri }
+        }),
+        NFTAction::Withdraw => nft.withdraw(),
+        NFTAction::ClaimRefund => nft.claim_refund(value).map(|nft_event| {
+            charged = value;
+            nft_event
+        }),
+        NFTAction::SetUpcomingPhases { phases } => nft.set_upcoming_phases(phases),
+        NFTAction::RedeemVoucher { voucher, signature } => nft
+            .redeem_voucher(voucher, &signature)
+            .map(|(nft_event, price)| {
+                charged = price;
+                nft_event
+            }),
+        NFTAction::SetMerkleRoot { root } => nft.check_collection_owner().map(|_| {
+            nft.merkle_root = root;
+            NFTEvent::MerkleRootSet { root }
+        }),
+        NFTAction::UpdateCollection(update) => nft
+            .check_collection_owner()
+            .map(|_| NFTEvent::CollectionUpdated(nft.update_collection(update))),
     };
+
+    // Only the price of a paid mint is kept, the rest of the value is returned.
+    // Gear rejects values below the existential deposit, so such remainders are
+    // kept for the sender to claim later.
+    let refund = value - charged;
+    if refund > 0 && msg::send_bytes(msg::source(), [], refund).is_err() {
+        let pending = nft.refunds.entry(msg::source()).or_default();
+        *pending = pending.saturating_add(refund);
+    }
+
+    reply(result).expect("Failed to encode or reply with `Result<NFTEvent, NFTError>`");
 }
 
 pub trait MyNFTCore: NFTCore {
-    fn mint(&mut self, token_metadata: TokenMetadata) -> NFTTransfer;
+    fn mint(
+        &mut self,
+        to: &ActorId,
+        token_metadata: TokenMetadata,
+        transferable: Option<bool>,
+        attributes: Attributes,
+    ) -> NFTTransfer;
+    fn burn(&mut self, token_id: TokenId) -> NFTTransfer;
+    fn transfer(&mut self, to: &ActorId, token_id: TokenId) -> NFTTransfer;
+    fn approve(&mut self, to: &ActorId, token_id: TokenId, expires_at: Option<u64>) -> NFTApproval;
+    fn transfer_payout(
+        &mut self,
+        to: &ActorId,
+        token_id: TokenId,
+        amount: u128,
+    ) -> NFTTransferPayout;
 }
 
 impl MyNFTCore for Contract {
-    fn mint(&mut self, token_metadata: TokenMetadata) -> NFTTransfer {
-        let transfer = NFTCore::mint(self, &msg::source(), self.token_id, Some(token_metadata));
+    fn mint(
+        &mut self,
+        to: &ActorId,
+        token_metadata: TokenMetadata,
+        transferable: Option<bool>,
+        attributes: Attributes,
+    ) -> NFTTransfer {
+        let transfer = NFTCore::mint(self, to, self.token_id, Some(token_metadata));
+        self.set_attributes(self.token_id, attributes);
+        if !transferable.unwrap_or(!self.soulbound) {
+            self.non_transferable.insert(self.token_id);
+        }
         self.token_id = self.token_id.saturating_add(U256::one());
         transfer
     }
+
+    /// Unlike [`NFTCore::burn`], doesn't require the caller to own the token,
+    /// so [`Role::Burner`] holders and operators can burn it too.
+    fn burn(&mut self, token_id: TokenId) -> NFTTransfer {
+        let owner = self
+            .token
+            .owner_by_id
+            .remove(&token_id)
+            .expect("Token must exist");
+        self.remove_from_owner(&owner, token_id);
+        self.token.token_metadata_by_id.remove(&token_id);
+        self.token.token_approvals.remove(&token_id);
+        self.approval_expirations.remove(&token_id);
+        self.non_transferable.remove(&token_id);
+        self.locks.remove(&token_id);
+        self.frozen_metadata.remove(&token_id);
+        self.attributes.remove(&token_id);
+
+        NFTTransfer {
+            from: owner,
+            to: ActorId::zero(),
+            token_id,
+        }
+    }
+
+    /// Unlike [`NFTCore::transfer`], doesn't check the caller, so operators can
+    /// transfer tokens too.
+    fn transfer(&mut self, to: &ActorId, token_id: TokenId) -> NFTTransfer {
+        let owner = self
+            .token
+            .owner_by_id
+            .insert(token_id, *to)
+            .expect("Token must exist");
+        self.remove_from_owner(&owner, token_id);
+        self.token
+            .tokens_for_owner
+            .entry(*to)
+            .or_default()
+            .push(token_id);
+        self.token.token_approvals.remove(&token_id);
+        self.approval_expirations.remove(&token_id);
+        self.locks.remove(&token_id);
+
+        NFTTransfer {
+            from: owner,
+            to: *to,
+            token_id,
+        }
+    }
+
+    /// Approves `to` until the `expires_at` block timestamp or indefinitely if
+    /// it's `None`.
+    fn approve(&mut self, to: &ActorId, token_id: TokenId, expires_at: Option<u64>) -> NFTApproval {
+        let owner = self.token.owner_by_id[&token_id];
+        self.token
+            .token_approvals
+            .entry(token_id)
+            .or_default()
+            .insert(*to);
+        if let Some(expires_at) = expires_at {
+            self.approval_expirations
+                .entry(token_id)
+                .or_default()
+                .insert(*to, expires_at);
+        } else if let Some(expirations) = self.approval_expirations.get_mut(&token_id) {
+            expirations.remove(to);
+        }
+
+        NFTApproval {
+            owner,
+            approved_account: *to,
+            token_id,
+        }
+    }
+
+    fn transfer_payout(
+        &mut self,
+        to: &ActorId,
+        token_id: TokenId,
+        amount: u128,
+    ) -> NFTTransferPayout {
+        let NFTTransfer { from, .. } = MyNFTCore::transfer(self, to, token_id);
+
+        NFTTransferPayout {
+            from,
+            to: *to,
+            token_id,
+            payouts: NFTCore::nft_payout(self, &from, amount),
+        }
+    }
 }
 
 impl Contract {
+    fn remove_from_owner(&mut self, owner: &ActorId, token_id: TokenId) {
+        if let Some(tokens) = self.token.tokens_for_owner.get_mut(owner) {
+            tokens.retain(|id| *id != token_id);
+            if tokens.is_empty() {
+                self.token.tokens_for_owner.remove(owner);
+            }
+        }
+    }
+
+    /// Checks `token_approvals` taking approval expirations into account.
+    fn is_approved(&self, account: &ActorId, token_id: TokenId) -> bool {
+        let is_approved = self
+            .token
+            .token_approvals
+            .get(&token_id)
+            .map(|approvals| approvals.contains(account))
+            .unwrap_or(false);
+        let is_expired = self
+            .approval_expirations
+            .get(&token_id)
+            .and_then(|expirations| expirations.get(account))
+            .map(|expires_at| exec::block_timestamp() >= *expires_at)
+            .unwrap_or(false);
+
+        is_approved && !is_expired
+    }
+
+    fn prune_expired_approvals(&mut self, token_id: TokenId) {
+        let now = exec::block_timestamp();
+        if let Some(expirations) = self.approval_expirations.get_mut(&token_id) {
+            let expired: Vec<ActorId> = expirations
+                .iter()
+                .filter(|(_account, expires_at)| now >= **expires_at)
+                .map(|(account, _expires_at)| *account)
+                .collect();
+            for account in expired {
+                expirations.remove(&account);
+                if let Some(approvals) = self.token.token_approvals.get_mut(&token_id) {
+                    approvals.remove(&account);
+                }
+            }
+            if expirations.is_empty() {
+                self.approval_expirations.remove(&token_id);
+            }
+        }
+    }
+
+    fn is_operator(&self, owner: &ActorId, operator: &ActorId) -> bool {
+        self.operators
+            .get(owner)
+            .map(|operators| operators.contains(operator))
+            .unwrap_or(false)
+    }
+
+    fn set_approval_for_all(
+        &mut self,
+        operator: ActorId,
+        approved: bool,
+    ) -> Result<NFTEvent, NFTError> {
+        let owner = msg::source();
+        if approved {
+            self.check_not_paused()?;
+            check_zero_address(&operator)?;
+            self.operators.entry(owner).or_default().insert(operator);
+        } else if let Some(operators) = self.operators.get_mut(&owner) {
+            operators.remove(&operator);
+            if operators.is_empty() {
+                self.operators.remove(&owner);
+            }
+        }
+
+        Ok(NFTEvent::ApprovalForAll {
+            owner,
+            operator,
+            approved,
+        })
+    }
+
     fn process_transaction(
         &mut self,
         transaction_id: u64,
-        action: impl FnOnce(&mut Contract) -> NFTEvent,
-    ) -> NFTEvent {
+        action: impl FnOnce(&mut Contract) -> Result<NFTEvent, NFTError>,
+    ) -> Result<NFTEvent, NFTError> {
         let transaction_hash = get_hash(&msg::source(), transaction_id);
 
-        if let Some(nft_event) = self.transactions.get(&transaction_hash) {
-            nft_event.clone()
+        if self.pending_transactions.contains(&transaction_hash) {
+            Err(NFTError::TransactionPending)
+        } else if let Some(nft_event) = self.transactions.get(&transaction_hash) {
+            Ok(nft_event.clone())
         } else {
-            let nft_event = action(self);
+            let nft_event = action(self)?;
+            self.cache_transaction(transaction_hash, nft_event.clone());
+
+            Ok(nft_event)
+        }
+    }
 
-            self.transactions
-                .insert(transaction_hash, nft_event.clone());
+    fn cache_transaction(&mut self, transaction_hash: H256, nft_event: NFTEvent) {
+        self.transactions.insert(transaction_hash, nft_event);
 
-            nft_event
+        if let Some(transaction_ttl) = self.transaction_ttl {
+            msg::send_delayed(
+                exec::program_id(),
+                NFTAction::Clear { transaction_hash },
+                0,
+                transaction_ttl,
+            )
+            .expect("Failed to schedule clearing of the cached transaction");
         }
     }
 
-    fn clear(&mut self, transaction_hash: H256) {
-        assert_eq!(
-            msg::source(),
-            exec::program_id(),
-            "Not allowed to clear transactions"
-        );
+    /// Unlike the other actions, awaits the recipient's reply, so the token is
+    /// locked in [`Contract::pending_tokens`] until the transfer is confirmed or
+    /// reverted along with the token's approvals.
+    async fn safe_transfer(
+        &mut self,
+        transaction_id: u64,
+        to: ActorId,
+        token_id: TokenId,
+        data: Vec<u8>,
+        require_callback: bool,
+    ) -> Result<NFTEvent, NFTError> {
+        let transaction_hash = get_hash(&msg::source(), transaction_id);
+
+        if self.pending_transactions.contains(&transaction_hash) {
+            return Err(NFTError::TransactionPending);
+        }
+        if let Some(nft_event) = self.transactions.get(&transaction_hash) {
+            return Ok(nft_event.clone());
+        }
+
+        self.check_not_paused()?;
+        self.prune_expired_approvals(token_id);
+        let from = self.check_can_transfer(&to, token_id)?;
+
+        if !require_callback {
+            let nft_event = NFTEvent::Transfer(MyNFTCore::transfer(self, &to, token_id));
+            self.cache_transaction(transaction_hash, nft_event.clone());
+
+            return Ok(nft_event);
+        }
+
+        let approvals = self.token.token_approvals.get(&token_id).cloned();
+        let expirations = self.approval_expirations.get(&token_id).cloned();
+        let transfer = MyNFTCore::transfer(self, &to, token_id);
+        self.pending_tokens.insert(token_id);
+        // Keeps other actions with the same transaction id from being cached
+        // while the reply is awaited.
+        self.pending_transactions.insert(transaction_hash);
+
+        let payload = NFTReceiverAction::OnNftReceived {
+            from,
+            token_id,
+            data,
+        };
+        let reply = msg::send_for_reply_as::<_, bool>(to, payload, 0)
+            .expect("Failed to send `NFTReceiverAction::OnNftReceived`")
+            .up_to(Some(SAFE_TRANSFER_TIMEOUT))
+            .expect("Failed to set the timeout of `NFTReceiverAction::OnNftReceived`")
+            .await;
+
+        self.pending_tokens.remove(&token_id);
+        self.pending_transactions.remove(&transaction_hash);
+
+        if !matches!(reply, Ok(true)) {
+            MyNFTCore::transfer(self, &from, token_id);
+            if let Some(approvals) = approvals {
+                self.token.token_approvals.insert(token_id, approvals);
+            }
+            if let Some(expirations) = expirations {
+                self.approval_expirations.insert(token_id, expirations);
+            }
+
+            return Err(NFTError::TransferRejected);
+        }
+
+        let nft_event = NFTEvent::Transfer(transfer);
+        self.cache_transaction(transaction_hash, nft_event.clone());
+
+        Ok(nft_event)
+    }
+
+    fn check_not_pending(&self, token_id: TokenId) -> Result<(), NFTError> {
+        if self.pending_tokens.contains(&token_id) {
+            return Err(NFTError::TokenPending);
+        }
+
+        Ok(())
+    }
+
+    fn check_not_locked(&self, token_id: TokenId) -> Result<(), NFTError> {
+        self.check_not_pending(token_id)?;
+        if self.is_locked(token_id) {
+            return Err(NFTError::TokenLocked);
+        }
+
+        Ok(())
+    }
+
+    fn is_locked(&self, token_id: TokenId) -> bool {
+        self.locks
+            .get(&token_id)
+            .map(|lock| {
+                lock.until
+                    .map_or(true, |until| exec::block_timestamp() < until)
+            })
+            .unwrap_or(false)
+    }
+
+    fn lock_token(&mut self, token_id: TokenId, until: Option<u64>) -> Result<NFTEvent, NFTError> {
+        let owner = self.token_owner(token_id)?;
+        let locker = msg::source();
+
+        let is_operator = owner == locker || self.is_operator(&owner, &locker);
+        if !is_operator && !self.is_approved(&locker, token_id) {
+            return Err(NFTError::NotOwnerOrApproved);
+        }
+        // The owner can't lift a lock of another account, so only operators the
+        // owner trusts with all its tokens can lock one forever.
+        if until.is_none() && !is_operator {
+            return Err(NFTError::NotOwnerOrOperator);
+        }
+        // Only the current locker can extend its own lock.
+        if self.is_locked(token_id) && self.locks[&token_id].locker != locker {
+            return Err(NFTError::TokenLocked);
+        }
+        self.locks.insert(token_id, TokenLock { locker, until });
+
+        Ok(NFTEvent::TokenLocked {
+            token_id,
+            locker,
+            until,
+        })
+    }
+
+    /// Lifts the lock of the caller or, for the token owner, a lock of an
+    /// account that is no longer approved for the token.
+    fn unlock_token(&mut self, token_id: TokenId) -> Result<NFTEvent, NFTError> {
+        let lock = *self.locks.get(&token_id).ok_or(NFTError::NotLocker)?;
+        let source = msg::source();
+
+        if lock.locker != source {
+            let owner = self.token_owner(token_id)?;
+            if owner != source
+                || self.is_approved(&lock.locker, token_id)
+                || self.is_operator(&owner, &lock.locker)
+            {
+                return Err(NFTError::NotLocker);
+            }
+        }
+        self.locks.remove(&token_id);
+
+        Ok(NFTEvent::TokenUnlocked { token_id })
+    }
+
+    fn clear(&mut self, transaction_hash: H256) -> Result<NFTEvent, NFTError> {
+        if msg::source() != exec::program_id() {
+            return Err(NFTError::NotAllowedToClear);
+        }
         self.transactions.remove(&transaction_hash);
+
+        Ok(NFTEvent::Cleared { transaction_hash })
+    }
+
+    fn check_not_paused(&self) -> Result<(), NFTError> {
+        if self.paused {
+            return Err(NFTError::Paused);
+        }
+
+        Ok(())
     }
 
-    fn check_constraints(&self) {
+    fn set_paused(&mut self, paused: bool) -> Result<NFTEvent, NFTError> {
+        if msg::source() != self.owner {
+            self.check_role(Role::Pauser)?;
+        }
+        self.paused = paused;
+
+        Ok(if paused {
+            NFTEvent::Paused
+        } else {
+            NFTEvent::Unpaused
+        })
+    }
+
+    fn check_collection_owner(&self) -> Result<(), NFTError> {
+        if msg::source() != self.owner {
+            return Err(NFTError::NotCollectionOwner);
+        }
+
+        Ok(())
+    }
+
+    fn update_collection(&mut self, update: CollectionUpdate) -> Collection {
+        let CollectionUpdate {
+            description,
+            banner_url,
+            logo_url,
+            external_link,
+            socials,
+        } = update;
+        let collection = &mut self.collection;
+
+        if let Some(description) = description {
+            collection.description = description;
+        }
+        if let Some(banner_url) = banner_url {
+            collection.banner_url = banner_url;
+        }
+        if let Some(logo_url) = logo_url {
+            collection.logo_url = logo_url;
+        }
+        if let Some(external_link) = external_link {
+            collection.external_link = external_link;
+        }
+        if let Some(socials) = socials {
+            collection.socials = socials;
+        }
+
+        collection.clone()
+    }
+
+    fn transfer_ownership(&mut self, new_owner: ActorId) -> Result<NFTEvent, NFTError> {
+        self.check_collection_owner()?;
+        check_zero_address(&new_owner)?;
+        self.pending_owner = Some(new_owner);
+
+        Ok(NFTEvent::OwnershipTransferStarted {
+            previous_owner: self.owner,
+            new_owner,
+        })
+    }
+
+    fn accept_ownership(&mut self) -> Result<NFTEvent, NFTError> {
+        let new_owner = msg::source();
+        if self.pending_owner != Some(new_owner) {
+            return Err(NFTError::NotPendingOwner);
+        }
+        self.pending_owner = None;
+        let previous_owner = mem::replace(&mut self.owner, new_owner);
+        self.revoke_role(Role::Admin, &previous_owner);
+        self.grant_role(Role::Admin, new_owner);
+
+        Ok(NFTEvent::OwnershipTransferred {
+            previous_owner,
+            new_owner,
+        })
+    }
+
+    /// Leaves the collection without an owner, disabling all owner-gated
+    /// actions, and drops the owner's [`Role::Admin`].
+    fn renounce_ownership(&mut self) -> Result<NFTEvent, NFTError> {
+        self.check_collection_owner()?;
+        self.pending_owner = None;
+        let previous_owner = mem::replace(&mut self.owner, ActorId::zero());
+        self.revoke_role(Role::Admin, &previous_owner);
+
+        Ok(NFTEvent::OwnershipTransferred {
+            previous_owner,
+            new_owner: ActorId::zero(),
+        })
+    }
+
+    /// Checks the constraints, charges the price and counts `amount` tokens in
+    /// the current mint phase and the allowlist allocation. Returns the charged
+    /// price.
+    fn prepare_mint(
+        &mut self,
+        amount: u32,
+        allowlist_proof: Option<&AllowlistProof>,
+    ) -> Result<u128, NFTError> {
+        let plan = self.check_constraints(amount, allowlist_proof)?;
+        let charged = self.charge(plan.price)?;
+
+        let source = msg::source();
+        *self.minted_by.entry(source).or_default() += amount;
+        if let Some(phase) = plan.phase {
+            *self.phase_mints.entry((phase, source)).or_default() += amount;
+        }
+        if plan.allowlisted {
+            *self.allowlist_claims.entry(source).or_default() += amount;
+        }
+
+        Ok(charged)
+    }
+
+    /// Keeps the `transferable` override and the attributes of a mint only for
+    /// [`Role::Minter`] holders, so buyers can't escape a soulbound collection
+    /// or set attributes reserved to [`Role::MetadataEditor`] holders.
+    fn minter_overrides(
+        &self,
+        transferable: Option<bool>,
+        attributes: Attributes,
+    ) -> (Option<bool>, Attributes) {
+        if self.has_role(Role::Minter, &msg::source()) {
+            (transferable, attributes)
+        } else {
+            (None, Vec::new())
+        }
+    }
+
+    /// Checks that the caller can mint and `amount` more tokens fit in
+    /// [`Constraints::max_mint_count`].
+    fn check_constraints(
+        &self,
+        amount: u32,
+        allowlist_proof: Option<&AllowlistProof>,
+    ) -> Result<MintPlan, NFTError> {
+        let minted = self.token.token_metadata_by_id.len() as u32;
+        self.check_max_mint_count(amount)?;
+
+        let source = msg::source();
+        if self.has_role(Role::Minter, &source) {
+            return Ok(MintPlan::default());
+        }
+        self.check_max_per_wallet(&source, amount)?;
+        let allowlisted = allowlist_proof
+            .map(|proof| self.check_allowlist_proof(&source, amount, proof))
+            .transpose()?
+            .is_some();
+
+        if !self.constraints.phases.is_empty() {
+            let (index, phase) = self
+                .constraints
+                .current_phase(exec::block_timestamp())
+                .ok_or(NFTError::MintClosed)?;
+            match &phase.eligibility {
+                Eligibility::Public => {}
+                Eligibility::Allowlist(accounts) if accounts.contains(&source) => {}
+                Eligibility::Allowlist(_) => return Err(NFTError::NotEligible),
+                Eligibility::MerkleAllowlist if allowlisted => {}
+                Eligibility::MerkleAllowlist => return Err(NFTError::NotEligible),
+                Eligibility::Closed => return Err(NFTError::MintClosed),
+            }
+            if let Some(max_per_wallet) = phase.max_per_wallet {
+                let phase_minted = self
+                    .phase_mints
+                    .get(&(index, source))
+                    .copied()
+                    .unwrap_or_default();
+                if amount > max_per_wallet.saturating_sub(phase_minted) {
+                    return Err(NFTError::MintLimitReached);
+                }
+            }
+
+            return Ok(MintPlan {
+                price: phase.price.saturating_mul(amount.into()),
+                phase: Some(index),
+                allowlisted,
+            });
+        }
+        if !self.constraints.public_mint && !allowlisted {
+            return Err(NFTError::NotAuthorizedMinter);
+        }
+
+        let price = (minted..minted.saturating_add(amount))
+            .map(|supply| self.price_at(supply))
+            .fold(0, u128::saturating_add);
+
+        Ok(MintPlan {
+            price,
+            phase: None,
+            allowlisted,
+        })
+    }
+
+    fn check_max_mint_count(&self, amount: u32) -> Result<(), NFTError> {
         if let Some(max_mint_count) = self.constraints.max_mint_count {
-            if max_mint_count <= self.token.token_metadata_by_id.len() as u32 {
-                panic!(
-                    "Mint impossible because max minting count {} limit exceeded",
-                    max_mint_count
-                );
+            let minted = self.token.token_metadata_by_id.len() as u32;
+            if amount > max_mint_count.saturating_sub(minted) {
+                return Err(NFTError::MaxMintCountExceeded);
             }
         }
 
-        let current_minter = msg::source();
-        let is_authorized_minter = self
+        Ok(())
+    }
+
+    /// Checks that `account` without [`Role::Minter`] can mint `amount` more
+    /// tokens under [`Constraints::max_per_wallet`].
+    fn check_max_per_wallet(&self, account: &ActorId, amount: u32) -> Result<(), NFTError> {
+        if self.has_role(Role::Minter, account) {
+            return Ok(());
+        }
+        if let Some(max_per_wallet) = self.constraints.max_per_wallet {
+            let minted_by = self.minted_by.get(account).copied().unwrap_or_default();
+            if amount > max_per_wallet.saturating_sub(minted_by) {
+                return Err(NFTError::MintLimitReached);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Mint phases don't apply since the voucher sets its own price, but the
+    /// redemption counts towards [`Constraints::max_per_wallet`] of the caller.
+    /// Returns the event along with the charged price.
+    fn redeem_voucher(
+        &mut self,
+        voucher: MintVoucher,
+        signature: &[u8; 64],
+    ) -> Result<(NFTEvent, u128), NFTError> {
+        self.check_not_paused()?;
+        self.check_max_mint_count(1)?;
+        if !self.has_role(Role::Minter, &voucher.minter) {
+            return Err(NFTError::NotAuthorizedMinter);
+        }
+        if exec::program_id() != voucher.nft_program_id {
+            return Err(NFTError::WrongProgramId);
+        }
+        if exec::block_timestamp() >= voucher.expiration_timestamp {
+            return Err(NFTError::DelegationExpired);
+        }
+        let voucher_hash = get_hash(&voucher.minter, voucher.nonce);
+        if self.used_vouchers.contains(&voucher_hash) {
+            return Err(NFTError::VoucherUsed);
+        }
+        let source = msg::source();
+        self.check_max_per_wallet(&source, 1)?;
+        let to = voucher.to.unwrap_or(source);
+        check_zero_address(&to)?;
+        if !verify_signature(&voucher.minter, &voucher.encode(), signature) {
+            return Err(NFTError::InvalidSignature);
+        }
+        let charged = self.charge(voucher.price)?;
+
+        self.used_vouchers.insert(voucher_hash);
+        *self.minted_by.entry(source).or_default() += 1;
+        let transfer = MyNFTCore::mint(
+            self,
+            &to,
+            voucher.token_metadata,
+            voucher.transferable,
+            voucher.attributes,
+        );
+
+        Ok((NFTEvent::Transfer(transfer), charged))
+    }
+
+    /// Checks that `proof` includes `account` in the allowlist and `amount`
+    /// more tokens fit in its allocation.
+    fn check_allowlist_proof(
+        &self,
+        account: &ActorId,
+        amount: u32,
+        proof: &AllowlistProof,
+    ) -> Result<(), NFTError> {
+        let root = self.merkle_root.ok_or(NFTError::InvalidProof)?;
+        if !merkle::verify(&root, merkle::leaf(account, proof.allocation), &proof.proof) {
+            return Err(NFTError::InvalidProof);
+        }
+
+        let claimed = self
+            .allowlist_claims
+            .get(account)
+            .copied()
+            .unwrap_or_default();
+        if amount > proof.allocation.saturating_sub(claimed) {
+            return Err(NFTError::MintLimitReached);
+        }
+
+        Ok(())
+    }
+
+    fn set_upcoming_phases(&mut self, phases: Vec<MintPhase>) -> Result<NFTEvent, NFTError> {
+        self.check_collection_owner()?;
+        let now = exec::block_timestamp();
+        if phases.iter().any(|phase| phase.start <= now) {
+            return Err(NFTError::InvalidPhases);
+        }
+
+        let mut schedule: Vec<_> = self
             .constraints
-            .authorized_minters
+            .phases
+            .iter()
+            .filter(|phase| phase.start <= now)
+            .cloned()
+            .collect();
+        schedule.extend(phases.iter().cloned());
+        check_phases(&schedule)?;
+        self.constraints.phases = schedule;
+
+        Ok(NFTEvent::UpcomingPhasesSet { phases })
+    }
+
+    /// Returns [`Contract::constraints`] with the current [`Role::Minter`]
+    /// holders.
+    fn io_constraints(&self) -> Constraints {
+        Constraints {
+            authorized_minters: self
+                .roles
+                .get(&Role::Minter)
+                .map(|minters| minters.iter().copied().collect())
+                .unwrap_or_default(),
+            ..self.constraints.clone()
+        }
+    }
+
+    fn price_at(&self, supply: u32) -> u128 {
+        self.constraints
+            .price_tiers
             .iter()
-            .any(|authorized_minter| authorized_minter.eq(&current_minter));
+            .rev()
+            .find(|tier| tier.supply <= supply)
+            .map(|tier| tier.price)
+            .unwrap_or_default()
+    }
+
+    /// Keeps `price` out of the attached value, which must cover it.
+    fn charge(&mut self, price: u128) -> Result<u128, NFTError> {
+        if msg::value() < price {
+            return Err(NFTError::InsufficientValue);
+        }
+        self.proceeds = self.proceeds.saturating_add(price);
+
+        Ok(price)
+    }
+
+    fn withdraw(&mut self) -> Result<NFTEvent, NFTError> {
+        self.check_collection_owner()?;
+        let amount = self.proceeds;
+        // Gear rejects values below the existential deposit, so small proceeds
+        // stay until they grow.
+        if amount > 0 && msg::send_bytes(self.owner, [], amount).is_err() {
+            return Err(NFTError::WithdrawalFailed);
+        }
+        self.proceeds = 0;
+
+        Ok(NFTEvent::Withdrawn { amount })
+    }
+
+    /// Sends the caller's pending refund together with the `attached` value.
+    fn claim_refund(&mut self, attached: u128) -> Result<NFTEvent, NFTError> {
+        let source = msg::source();
+        let amount = self
+            .refunds
+            .get(&source)
+            .copied()
+            .unwrap_or_default()
+            .saturating_add(attached);
+        if amount > 0 && msg::send_bytes(source, [], amount).is_err() {
+            return Err(NFTError::WithdrawalFailed);
+        }
+        self.refunds.remove(&source);
+
+        Ok(NFTEvent::RefundClaimed { amount })
+    }
+
+    fn has_role(&self, role: Role, account: &ActorId) -> bool {
+        self.roles
+            .get(&role)
+            .map(|holders| holders.contains(account))
+            .unwrap_or(false)
+    }
+
+    fn check_role(&self, role: Role) -> Result<(), NFTError> {
+        if !self.has_role(role, &msg::source()) {
+            return Err(NFTError::MissingRole(role));
+        }
 
-        if !is_authorized_minter {
-            panic!(
-                "Current minter {:?} is not authorized at initialization",
-                current_minter
-            );
+        Ok(())
+    }
+
+    fn grant_role(&mut self, role: Role, account: ActorId) {
+        self.roles.entry(role).or_default().insert(account);
+    }
+
+    fn revoke_role(&mut self, role: Role, account: &ActorId) {
+        if let Some(holders) = self.roles.get_mut(&role) {
+            holders.remove(account);
         }
     }
+
+    fn token_owner(&self, token_id: TokenId) -> Result<ActorId, NFTError> {
+        self.token
+            .owner_by_id
+            .get(&token_id)
+            .copied()
+            .ok_or(NFTError::TokenDoesNotExist)
+    }
+
+    /// Checks that the caller can change the token metadata, which is allowed to
+    /// [`Role::MetadataEditor`] holders and, if
+    /// [`Contract::owner_can_update_metadata`] is set, to the token owner.
+    /// Pending tokens can't be changed since a rejected
+    /// [`NFTAction::SafeTransfer`] doesn't revert the changes.
+    fn check_metadata_editor(&self, token_id: TokenId) -> Result<(), NFTError> {
+        let owner = self.token_owner(token_id)?;
+        self.check_not_pending(token_id)?;
+
+        if self.all_metadata_frozen || self.frozen_metadata.contains(&token_id) {
+            return Err(NFTError::MetadataFrozen);
+        }
+        if !(self.owner_can_update_metadata && owner == msg::source()) {
+            self.check_role(Role::MetadataEditor)?;
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the token attributes; the last value wins for repeated keys.
+    fn set_attributes(&mut self, token_id: TokenId, attributes: Attributes) {
+        if attributes.is_empty() {
+            self.attributes.remove(&token_id);
+        } else {
+            self.attributes
+                .insert(token_id, attributes.into_iter().collect());
+        }
+    }
+
+    fn token_attributes(&self, token_id: TokenId) -> Attributes {
+        self.attributes
+            .get(&token_id)
+            .map(|attributes| {
+                attributes
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn check_owner(&self, token_id: TokenId) -> Result<ActorId, NFTError> {
+        let owner = self.token_owner(token_id)?;
+
+        if owner != msg::source() {
+            return Err(NFTError::NotOwner);
+        }
+
+        Ok(owner)
+    }
+
+    fn check_can_burn(&self, token_id: TokenId) -> Result<(), NFTError> {
+        self.check_not_locked(token_id)?;
+        let owner = self.token_owner(token_id)?;
+        let source = msg::source();
+
+        if owner != source
+            && !self.is_operator(&owner, &source)
+            && !self.has_role(Role::Burner, &source)
+        {
+            return Err(NFTError::NotOwner);
+        }
+
+        Ok(())
+    }
+
+    fn check_transferable(&self, token_id: TokenId) -> Result<(), NFTError> {
+        if self.non_transferable.contains(&token_id) {
+            return Err(NFTError::NonTransferable);
+        }
+
+        Ok(())
+    }
+
+    fn check_can_transfer(&self, to: &ActorId, token_id: TokenId) -> Result<ActorId, NFTError> {
+        self.check_not_locked(token_id)?;
+        self.check_transferable(token_id)?;
+        let owner = self.token_owner(token_id)?;
+        let source = msg::source();
+
+        if owner != source
+            && !self.is_approved(&source, token_id)
+            && !self.is_operator(&owner, &source)
+        {
+            return Err(NFTError::NotOwnerOrApproved);
+        }
+        check_zero_address(to)?;
+
+        Ok(owner)
+    }
+
+    fn delegation_nonce(&self, owner: &ActorId) -> u64 {
+        self.delegation_nonces
+            .get(owner)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns the new nonce.
+    fn increment_nonce(&mut self, owner: ActorId) -> u64 {
+        let nonce = self.delegation_nonces.entry(owner).or_default();
+        *nonce += 1;
+        *nonce
+    }
+
+    fn check_delegated_approve(
+        &self,
+        message: &DelegatedApproveMessage,
+        signature: &[u8; 64],
+    ) -> Result<(), NFTError> {
+        if self.token_owner(message.token_id)? != message.token_owner_id {
+            return Err(NFTError::WrongTokenOwner);
+        }
+        if msg::source() != message.approved_actor_id {
+            return Err(NFTError::NotApprovedActor);
+        }
+        self.check_transferable(message.token_id)?;
+        self.check_delegation(
+            &message.token_owner_id,
+            &message.nft_program_id,
+            message.expiration_timestamp,
+            message.nonce,
+        )?;
+        check_zero_address(&message.approved_actor_id)?;
+
+        if !verify_signature(&message.token_owner_id, &message.encode(), signature) {
+            return Err(NFTError::InvalidSignature);
+        }
+
+        Ok(())
+    }
+
+    fn check_delegated_transfer(
+        &self,
+        message: &DelegatedTransferMessage,
+        signature: &[u8; 64],
+    ) -> Result<(), NFTError> {
+        self.check_not_locked(message.token_id)?;
+        if self.token_owner(message.token_id)? != message.token_owner_id {
+            return Err(NFTError::WrongTokenOwner);
+        }
+        self.check_transferable(message.token_id)?;
+        self.check_delegation(
+            &message.token_owner_id,
+            &message.nft_program_id,
+            message.expiration_timestamp,
+            message.nonce,
+        )?;
+        check_zero_address(&message.to)?;
+
+        if !verify_signature(&message.token_owner_id, &message.encode(), signature) {
+            return Err(NFTError::InvalidSignature);
+        }
+
+        Ok(())
+    }
+
+    /// Checks the fields shared by all delegated messages signed by `signer`.
+    fn check_delegation(
+        &self,
+        signer: &ActorId,
+        nft_program_id: &ActorId,
+        expiration_timestamp: u64,
+        nonce: u64,
+    ) -> Result<(), NFTError> {
+        if exec::program_id() != *nft_program_id {
+            return Err(NFTError::WrongProgramId);
+        }
+        if exec::block_timestamp() >= expiration_timestamp {
+            return Err(NFTError::DelegationExpired);
+        }
+        if nonce != self.delegation_nonce(signer) {
+            return Err(NFTError::InvalidNonce);
+        }
+
+        Ok(())
+    }
+}
+
+fn check_zero_address(account: &ActorId) -> Result<(), NFTError> {
+    if *account == ActorId::zero() {
+        return Err(NFTError::ZeroAddress);
+    }
+
+    Ok(())
+}
+
+fn check_expiration(expires_at: Option<u64>) -> Result<(), NFTError> {
+    if matches!(expires_at, Some(expires_at) if expires_at <= exec::block_timestamp()) {
+        return Err(NFTError::ApprovalExpired);
+    }
+
+    Ok(())
+}
+
+/// Checks that `phases` are sorted, don't overlap and don't end before they
+/// start.
+fn check_phases(phases: &[MintPhase]) -> Result<(), NFTError> {
+    let mut previous_end = 0;
+    for phase in phases {
+        if phase.start < previous_end || phase.end <= phase.start {
+            return Err(NFTError::InvalidPhases);
+        }
+        previous_end = phase.end;
+    }
+
+    Ok(())
+}
+
+fn verify_signature(signer: &ActorId, message: &[u8], signature: &[u8; 64]) -> bool {
+    let signer: [u8; 32] = (*signer).into();
+    let (Ok(public_key), Ok(signature)) = (
+        PublicKey::from_bytes(&signer),
+        Signature::from_bytes(signature),
+    ) else {
+        return false;
+    };
+
+    public_key
+        .verify_simple(SIGNING_CONTEXT, message, &signature)
+        .is_ok()
 }
 
 fn static_mut_state() -> &'static Contract {
@@ -255,31 +1504,116 @@ fn reply(payload: impl Encode) -> GstdResult<MessageId> {
     msg::reply(payload, 0)
 }
 
-pub fn get_hash(account: &ActorId, transaction_id: u64) -> H256 {
-    let account: [u8; 32] = (*account).into();
-    let transaction_id = transaction_id.to_be_bytes();
-    sp_core_hashing::blake2_256(&[account.as_slice(), transaction_id.as_slice()].concat()).into()
-}
-
 impl From<&Contract> for IoNFT {
     fn from(value: &Contract) -> Self {
         let Contract {
             token,
             token_id,
             owner,
+            pending_owner,
             transactions,
+            collection,
+            proceeds,
+            refunds,
+            phase_mints,
+            merkle_root,
+            allowlist_claims,
+            minted_by,
+            used_vouchers,
+            roles,
+            paused,
+            operators,
+            approval_expirations,
+            delegation_nonces,
+            non_transferable,
+            locks,
+            frozen_metadata,
+            all_metadata_frozen,
+            attributes,
             ..
         } = value;
 
+        let mut token: IoNFTState = token.into();
+        // Expired approvals are pruned lazily, so hide them here.
+        for (token_id, approvals) in &mut token.token_approvals {
+            approvals.retain(|account| value.is_approved(account, *token_id));
+        }
+        token
+            .token_approvals
+            .retain(|(_token_id, approvals)| !approvals.is_empty());
+
+        let approval_expirations = approval_expirations
+            .iter()
+            .map(|(token_id, expirations)| {
+                let expirations = expirations
+                    .iter()
+                    .map(|(account, expires_at)| (*account, *expires_at))
+                    .collect();
+                (*token_id, expirations)
+            })
+            .collect();
+
         let transactions = transactions
             .iter()
             .map(|(key, event)| (*key, event.clone()))
             .collect();
+
+        let roles = roles
+            .iter()
+            .map(|(role, holders)| (*role, holders.iter().copied().collect()))
+            .collect();
+
+        let operators = operators
+            .iter()
+            .map(|(owner, operators)| (*owner, operators.iter().copied().collect()))
+            .collect();
         Self {
-            token: token.into(),
+            token,
+            collection: collection.clone(),
+            proceeds: *proceeds,
+            refunds: refunds
+                .iter()
+                .map(|(account, amount)| (*account, *amount))
+                .collect(),
+            constraints: value.io_constraints(),
+            phase_mints: phase_mints
+                .iter()
+                .map(|(key, minted)| (*key, *minted))
+                .collect(),
+            merkle_root: *merkle_root,
+            allowlist_claims: allowlist_claims
+                .iter()
+                .map(|(account, claimed)| (*account, *claimed))
+                .collect(),
+            minted_by: minted_by
+                .iter()
+                .map(|(account, minted)| (*account, *minted))
+                .collect(),
+            used_vouchers: used_vouchers.iter().copied().collect(),
             token_id: *token_id,
             owner: *owner,
+            pending_owner: *pending_owner,
             transactions,
+            roles,
+            paused: *paused,
+            operators,
+            approval_expirations,
+            delegation_nonces: delegation_nonces
+                .iter()
+                .map(|(owner, nonce)| (*owner, *nonce))
+                .collect(),
+            non_transferable: non_transferable.iter().copied().collect(),
+            locks: locks
+                .iter()
+                .filter(|(token_id, _lock)| value.is_locked(**token_id))
+                .map(|(token_id, lock)| (*token_id, *lock))
+                .collect(),
+            frozen_metadata: frozen_metadata.iter().copied().collect(),
+            all_metadata_frozen: *all_metadata_frozen,
+            attributes: attributes
+                .keys()
+                .map(|token_id| (*token_id, value.token_attributes(*token_id)))
+                .collect(),
         }
     }
 }
@@ -290,9 +1624,12 @@ impl From<&Contract> for State {
             token,
             token_id,
             owner,
+            pending_owner,
             transactions,
             collection,
-            constraints,
+            paused,
+            non_transferable,
+            ..
         } = value;
 
         let owners = token
@@ -317,6 +1654,8 @@ impl From<&Contract> for State {
                     description: metadata.description.clone(),
                     media_url: metadata.media.clone(),
                     attrib_url: metadata.reference.clone(),
+                    transferable: !non_transferable.contains(id),
+                    attributes: value.token_attributes(*id),
                 };
                 (*id, nft)
             })
@@ -328,8 +1667,10 @@ impl From<&Contract> for State {
             nonce: *token_id,
             owners,
             owner: *owner,
+            pending_owner: *pending_owner,
             transactions,
-            constraints: constraints.clone(),
+            paused: *paused,
+            constraints: value.io_constraints(),
         }
     }
 }
@@ -5,8 +5,10 @@ use gear_lib::non_fungible_token::{
     token::{Token, TokenId},
 };
 use gmeta::{metawasm, Metadata};
-use gstd::{ActorId, Vec};
-use nft_io::NFTMetadata;
+use gstd::{prelude::*, ActorId};
+use nft_io::{
+    AttributeValue, Attributes, Collection, MintPhase, NFTMetadata, Role, TokenInfo, TokenLock,
+};
 
 #[metawasm]
 pub mod metafns {
@@ -20,8 +22,15 @@ pub mod metafns {
         }
     }
 
-    pub fn token(state: State, token_id: TokenId) -> Token {
-        token_helper(&token_id, &state)
+    pub fn collection(state: State) -> Collection {
+        state.collection
+    }
+
+    pub fn token(state: State, token_id: TokenId) -> TokenInfo {
+        TokenInfo {
+            token: token_helper(&token_id, &state),
+            transferable: !state.non_transferable.contains(&token_id),
+        }
     }
 
     pub fn tokens_for_owner(state: State, owner: ActorId) -> Vec<Token> {
@@ -93,6 +102,121 @@ pub mod metafns {
             })
             .collect()
     }
+
+    pub fn operators_for_owner(state: State, owner: ActorId) -> Vec<ActorId> {
+        state
+            .operators
+            .into_iter()
+            .find(|(id, _operators)| owner.eq(id))
+            .map(|(_owner, operators)| operators)
+            .unwrap_or_default()
+    }
+
+    pub fn delegation_nonce(state: State, owner: ActorId) -> u64 {
+        state
+            .delegation_nonces
+            .into_iter()
+            .find(|(id, _nonce)| owner.eq(id))
+            .map(|(_owner, nonce)| nonce)
+            .unwrap_or_default()
+    }
+
+    /// Returns `None` if the token doesn't exist.
+    pub fn is_transferable(state: State, token_id: TokenId) -> Option<bool> {
+        state
+            .token
+            .owner_by_id
+            .iter()
+            .any(|(id, _owner)| token_id.eq(id))
+            .then(|| !state.non_transferable.contains(&token_id))
+    }
+
+    pub fn token_lock(state: State, token_id: TokenId) -> Option<TokenLock> {
+        state
+            .locks
+            .into_iter()
+            .find(|(id, _lock)| token_id.eq(id))
+            .map(|(_token_id, lock)| lock)
+    }
+
+    pub fn token_attributes(state: State, token_id: TokenId) -> Attributes {
+        state
+            .attributes
+            .into_iter()
+            .find(|(id, _attributes)| token_id.eq(id))
+            .map(|(_token_id, attributes)| attributes)
+            .unwrap_or_default()
+    }
+
+    pub fn tokens_with_attribute(state: State, key: String, value: AttributeValue) -> Vec<TokenId> {
+        state
+            .attributes
+            .into_iter()
+            .filter(|(_token_id, attributes)| {
+                attributes.iter().any(|(k, v)| key.eq(k) && value.eq(v))
+            })
+            .map(|(token_id, _attributes)| token_id)
+            .collect()
+    }
+
+    /// Composes the base URI with the token `reference` or, if it's empty, the
+    /// token id.
+    pub fn token_uri(state: State, token_id: TokenId) -> Option<String> {
+        state
+            .token
+            .token_metadata_by_id
+            .iter()
+            .find(|(id, _metadata)| token_id.eq(id))
+            .map(|(_token_id, metadata)| {
+                let reference = metadata
+                    .as_ref()
+                    .map(|metadata| metadata.reference.clone())
+                    .unwrap_or_default();
+                if reference.is_empty() {
+                    format!("{}{token_id}", state.token.base_uri)
+                } else {
+                    format!("{}{reference}", state.token.base_uri)
+                }
+            })
+    }
+
+    pub fn current_phase(state: State, timestamp: u64) -> Option<MintPhase> {
+        state
+            .constraints
+            .current_phase(timestamp)
+            .map(|(_index, phase)| phase.clone())
+    }
+
+    /// Returns the first phase starting after the block `timestamp`.
+    pub fn next_phase(state: State, timestamp: u64) -> Option<MintPhase> {
+        state
+            .constraints
+            .phases
+            .into_iter()
+            .find(|phase| phase.start > timestamp)
+    }
+
+    pub fn minted_by(state: State, account: ActorId) -> u32 {
+        state
+            .minted_by
+            .into_iter()
+            .find(|(id, _minted)| account.eq(id))
+            .map(|(_account, minted)| minted)
+            .unwrap_or_default()
+    }
+
+    pub fn is_paused(state: State) -> bool {
+        state.paused
+    }
+
+    pub fn role_holders(state: State, role: Role) -> Vec<ActorId> {
+        state
+            .roles
+            .into_iter()
+            .find(|(r, _holders)| role.eq(r))
+            .map(|(_role, holders)| holders)
+            .unwrap_or_default()
+    }
 }
 
 fn token_helper(token_id: &TokenId, state: &<NFTMetadata as Metadata>::State) -> Token {
@@ -1,10 +1,10 @@
-use gear_lib::non_fungible_token::delegated::DelegatedApproveMessage;
-use gear_lib::non_fungible_token::io::*;
+use gear_lib::non_fungible_token::{io::*, token::TokenMetadata};
 use gstd::{ActorId, Encode};
 use gtest::System;
 mod utils;
 use hex_literal::hex;
 use nft_io::*;
+use nft_state::metafns;
 use sp_core::{sr25519::Pair as Sr25519Pair, Pair};
 use utils::*;
 
@@ -18,71 +18,87 @@ fn mint_success() {
     let nft = sys.get_program(1);
     let transaction_id: u64 = 0;
     let res = mint(&nft, transaction_id, USERS[0]);
-    let message = NFTEvent::Transfer(NFTTransfer {
+    let event = NFTEvent::Transfer(NFTTransfer {
         from: ZERO_ID.into(),
         to: USERS[0].into(),
         token_id: 0.into(),
-    })
-    .encode();
-    assert!(res.contains(&(USERS[0], message)));
+    });
+    assert_ok(&res, USERS[0], event);
 }
 
 #[test]
-fn mint_limit_exceed() {
+fn mint_to_success() {
     let sys = System::new();
-    sys.init_logger();
-    let nft = gtest::Program::current(&sys);
+    init_nft(&sys);
+    let nft = sys.get_program(1);
+    let res = mint_to(&nft, 0, USERS[0], USERS[1]);
+    let event = NFTEvent::Transfer(NFTTransfer {
+        from: ZERO_ID.into(),
+        to: USERS[1].into(),
+        token_id: 0.into(),
+    });
+    assert_ok(&res, USERS[0], event);
 
-    let collection = Collection {
-        name: String::from("MyToken"),
-        description: String::from("My token"),
-    };
+    // must fail since the caller is not an authorized minter
+    let res = mint_to(&nft, 0, USERS[1], USERS[1]);
+    assert_err(&res, USERS[1], NFTError::NotAuthorizedMinter);
+    // must fail since minting to the zero address
+    let res = mint_to(&nft, 1, USERS[0], ZERO_ID);
+    assert_err(&res, USERS[0], NFTError::ZeroAddress);
+}
+
+#[test]
+fn batch_mint() {
+    let sys = System::new();
+    let mut config = init_config();
+    config.constraints.max_mint_count = Some(2);
+    init_nft_with(&sys, config);
+    let nft = sys.get_program(1);
+
+    // must fail since the whole batch doesn't fit in the limit
+    let res = batch_mint(&nft, 0, USERS[0], &[USERS[1], USERS[2], USERS[1]]);
+    assert_err(&res, USERS[0], NFTError::MaxMintCountExceeded);
 
-    let init_nft = InitNFT {
-        collection,
-        royalties: None,
-        constraints: Constraints {
-            max_mint_count: Some(1),
-            authorized_minters: vec![USERS[0].into()],
+    let res = batch_mint(&nft, 1, USERS[0], &[USERS[1], USERS[2]]);
+    let event = NFTEvent::BatchMint(vec![
+        NFTTransfer {
+            from: ZERO_ID.into(),
+            to: USERS[1].into(),
+            token_id: 0.into(),
         },
-    };
+        NFTTransfer {
+            from: ZERO_ID.into(),
+            to: USERS[2].into(),
+            token_id: 1.into(),
+        },
+    ]);
+    assert_ok(&res, USERS[0], event);
 
-    let res = nft.send(USERS[0], init_nft);
+    let res = mint(&nft, 2, USERS[0]);
+    assert_err(&res, USERS[0], NFTError::MaxMintCountExceeded);
+}
 
-    assert!(!res.main_failed());
+#[test]
+fn mint_limit_exceed() {
+    let sys = System::new();
+    let mut config = init_config();
+    config.constraints.max_mint_count = Some(1);
+    init_nft_with(&sys, config);
 
     let nft = sys.get_program(1);
     let transaction_id: u64 = 0;
     let res = mint(&nft, transaction_id, USERS[0]);
     assert!(!res.main_failed());
     let res = mint(&nft, transaction_id + 1, USERS[1]);
-    assert!(res.main_failed())
+    assert_err(&res, USERS[1], NFTError::MaxMintCountExceeded);
 }
 
 #[test]
 fn mint_authorized() {
     let sys = System::new();
-    sys.init_logger();
-    let nft = gtest::Program::current(&sys);
-
-    let collection = Collection {
-        name: String::from("MyToken"),
-        description: String::from("My token"),
-    };
-
-    let authorized_minters: Vec<ActorId> = vec![USERS[0].into()];
-    let init_nft = InitNFT {
-        collection,
-        royalties: None,
-        constraints: Constraints {
-            max_mint_count: None,
-            authorized_minters,
-        },
-    };
-
-    let res = nft.send(USERS[0], init_nft);
-
-    assert!(!res.main_failed());
+    let mut config = init_config();
+    config.constraints.max_mint_count = None;
+    init_nft_with(&sys, config);
 
     let nft = sys.get_program(1);
     let transaction_id: u64 = 0;
@@ -95,70 +111,279 @@ fn mint_authorized() {
 #[test]
 fn mint_not_authorized() {
     let sys = System::new();
-    sys.init_logger();
-    let nft = gtest::Program::current(&sys);
+    let mut config = init_config();
+    config.constraints.max_mint_count = None;
+    init_nft_with(&sys, config);
 
-    let collection = Collection {
-        name: String::from("MyToken"),
-        description: String::from("My token"),
+    let nft = sys.get_program(1);
+    let transaction_id: u64 = 0;
+    let res = mint(&nft, transaction_id, USERS[0]);
+    assert!(!res.main_failed());
+    let res = mint(&nft, transaction_id + 1, USERS[1]);
+    assert_err(&res, USERS[1], NFTError::NotAuthorizedMinter);
+}
+
+#[test]
+fn mint_added() {
+    let sys = System::new();
+    let mut config = init_config();
+    config.constraints.max_mint_count = None;
+    init_nft_with(&sys, config);
+
+    let nft = sys.get_program(1);
+    let transaction_id: u64 = 0;
+    let res = add_minter(&nft, transaction_id, USERS[1].into(), USERS[0]);
+    let event = NFTEvent::MinterAdded {
+        minter_id: USERS[1].into(),
     };
+    assert_ok(&res, USERS[0], event);
+    assert!(!mint(&nft, transaction_id, USERS[1]).main_failed());
 
-    let authorized_minters: Vec<ActorId> = vec![USERS[0].into()];
-    let init_nft = InitNFT {
-        collection,
-        royalties: None,
-        constraints: Constraints {
-            max_mint_count: None,
-            authorized_minters,
-        },
+    // must fail since only admins can add minters
+    let res = add_minter(&nft, transaction_id + 1, USERS[2].into(), USERS[1]);
+    assert_err(&res, USERS[1], NFTError::MissingRole(Role::Admin));
+
+    let res = add_minter(&nft, transaction_id + 1, 5.into(), 7);
+    assert_err(&res, 7, NFTError::MissingRole(Role::Admin));
+}
+
+#[test]
+fn minters_management() {
+    let sys = System::new();
+    let mut config = init_config();
+    config.constraints.max_mint_count = Some(1);
+    init_nft_with(&sys, config);
+    let nft = sys.get_program(1);
+
+    let mut transaction_id: u64 = 0;
+    assert!(!mint(&nft, transaction_id, USERS[0]).main_failed());
+
+    // minters can be managed after the supply is exhausted
+    transaction_id += 1;
+    let res = add_minter(&nft, transaction_id, USERS[1].into(), USERS[0]);
+    let event = NFTEvent::MinterAdded {
+        minter_id: USERS[1].into(),
+    };
+    assert_ok(&res, USERS[0], event);
+
+    transaction_id += 1;
+    let res = remove_minter(&nft, transaction_id, USERS[0].into(), USERS[0]);
+    let event = NFTEvent::MinterRemoved {
+        minter_id: USERS[0].into(),
     };
+    assert_ok(&res, USERS[0], event);
+
+    transaction_id += 1;
+    let res = set_minters(&nft, transaction_id, &[USERS[2]], USERS[1]);
+    assert_err(&res, USERS[1], NFTError::MissingRole(Role::Admin));
 
-    let res = nft.send(USERS[0], init_nft);
+    transaction_id += 1;
+    let res = set_minters(&nft, transaction_id, &[USERS[2]], USERS[0]);
+    let event = NFTEvent::MintersSet {
+        minters: vec![USERS[2].into()],
+    };
+    assert_ok(&res, USERS[0], event);
 
+    // `SetMinters` replaced all previous minters
+    transaction_id += 1;
+    assert!(!burn(&nft, transaction_id, USERS[0], 0).main_failed());
+    transaction_id += 1;
+    let res = mint(&nft, transaction_id, USERS[1]);
+    assert_err(&res, USERS[1], NFTError::NotAuthorizedMinter);
+    let res = mint(&nft, transaction_id, USERS[2]);
     assert!(!res.main_failed());
+}
+
+#[test]
+fn roles() {
+    let sys = System::new();
+    init_nft(&sys);
+    let nft = sys.get_program(1);
+    assert!(!mint(&nft, 0, USERS[0]).main_failed());
+
+    // must fail since the caller is not the token owner
+    let res = burn(&nft, 1, USERS[1], 0);
+    assert_err(&res, USERS[1], NFTError::NotOwner);
+
+    // must fail since only admins can grant roles
+    let res = grant_role(&nft, USERS[1], Role::Burner, USERS[1]);
+    assert_err(&res, USERS[1], NFTError::MissingRole(Role::Admin));
+
+    let res = grant_role(&nft, USERS[0], Role::Burner, USERS[1]);
+    let event = NFTEvent::RoleGranted {
+        role: Role::Burner,
+        account: USERS[1].into(),
+    };
+    assert_ok(&res, USERS[0], event);
+
+    let res = burn(&nft, 2, USERS[1], 0);
+    let event = NFTEvent::Transfer(NFTTransfer {
+        from: USERS[0].into(),
+        to: ZERO_ID.into(),
+        token_id: 0.into(),
+    });
+    assert_ok(&res, USERS[1], event);
+
+    let res = revoke_role(&nft, USERS[0], Role::Burner, USERS[1]);
+    let event = NFTEvent::RoleRevoked {
+        role: Role::Burner,
+        account: USERS[1].into(),
+    };
+    assert_ok(&res, USERS[0], event);
+
+    assert!(!mint(&nft, 3, USERS[0]).main_failed());
+    let res = burn(&nft, 4, USERS[1], 1);
+    assert_err(&res, USERS[1], NFTError::NotOwner);
+
+    let res = renounce_role(&nft, USERS[0], Role::Minter);
+    let event = NFTEvent::RoleRevoked {
+        role: Role::Minter,
+        account: USERS[0].into(),
+    };
+    assert_ok(&res, USERS[0], event);
+
+    let res = mint(&nft, 5, USERS[0]);
+    assert_err(&res, USERS[0], NFTError::NotAuthorizedMinter);
+}
 
+#[test]
+fn transactions_expire() {
+    let sys = System::new();
+    let mut config = init_config();
+    config.constraints.max_mint_count = None;
+    config.transaction_ttl = Some(5);
+    init_nft_with(&sys, config);
     let nft = sys.get_program(1);
+
     let transaction_id: u64 = 0;
+    let transaction_hash = get_hash(&USERS[0].into(), transaction_id);
+    assert!(!mint(&nft, transaction_id, USERS[0]).main_failed());
+
+    let res = is_transaction_cached(&nft, USERS[1], transaction_hash);
+    let event = NFTEvent::IsTransactionCached {
+        transaction_hash,
+        cached: true,
+    };
+    assert_ok(&res, USERS[1], event);
+
+    sys.spend_blocks(5);
+
+    let res = is_transaction_cached(&nft, USERS[1], transaction_hash);
+    let event = NFTEvent::IsTransactionCached {
+        transaction_hash,
+        cached: false,
+    };
+    assert_ok(&res, USERS[1], event);
+
+    // the same `transaction_id` mints a new token once the cached one expires
     let res = mint(&nft, transaction_id, USERS[0]);
-    assert!(!res.main_failed());
-    let res = mint(&nft, transaction_id + 1, USERS[1]);
-    assert!(res.main_failed())
+    let event = NFTEvent::Transfer(NFTTransfer {
+        from: ZERO_ID.into(),
+        to: USERS[0].into(),
+        token_id: 1.into(),
+    });
+    assert_ok(&res, USERS[0], event);
 }
 
 #[test]
-fn mint_added() {
+fn ownership_transfer() {
     let sys = System::new();
-    sys.init_logger();
-    let nft = gtest::Program::current(&sys);
+    init_nft(&sys);
+    let nft = sys.get_program(1);
 
-    let collection = Collection {
-        name: String::from("MyToken"),
-        description: String::from("My token"),
+    // must fail since the caller is not the collection owner
+    let res = transfer_ownership(&nft, USERS[1], USERS[1]);
+    assert_err(&res, USERS[1], NFTError::NotCollectionOwner);
+    // must fail since ownership can't be transferred to the zero address
+    let res = transfer_ownership(&nft, USERS[0], ZERO_ID);
+    assert_err(&res, USERS[0], NFTError::ZeroAddress);
+
+    let res = transfer_ownership(&nft, USERS[0], USERS[1]);
+    let event = NFTEvent::OwnershipTransferStarted {
+        previous_owner: USERS[0].into(),
+        new_owner: USERS[1].into(),
     };
+    assert_ok(&res, USERS[0], event);
 
-    let authorized_minters: Vec<ActorId> = vec![USERS[0].into()];
-    let init_nft = InitNFT {
-        collection,
-        royalties: None,
-        constraints: Constraints {
-            max_mint_count: None,
-            authorized_minters,
-        },
+    // must fail since the caller is not the pending owner
+    let res = accept_ownership(&nft, USERS[2]);
+    assert_err(&res, USERS[2], NFTError::NotPendingOwner);
+
+    let res = accept_ownership(&nft, USERS[1]);
+    let event = NFTEvent::OwnershipTransferred {
+        previous_owner: USERS[0].into(),
+        new_owner: USERS[1].into(),
     };
+    assert_ok(&res, USERS[1], event);
 
-    let res = nft.send(USERS[0], init_nft);
+    // must fail since the previous owner lost its powers
+    let res = renounce_ownership(&nft, USERS[0]);
+    assert_err(&res, USERS[0], NFTError::NotCollectionOwner);
+    let res = grant_role(&nft, USERS[0], Role::Minter, USERS[0]);
+    assert_err(&res, USERS[0], NFTError::MissingRole(Role::Admin));
 
-    assert!(!res.main_failed());
+    // the admin role came with the ownership
+    let res = grant_role(&nft, USERS[1], Role::Minter, USERS[2]);
+    let event = NFTEvent::RoleGranted {
+        role: Role::Minter,
+        account: USERS[2].into(),
+    };
+    assert_ok(&res, USERS[1], event);
+
+    let res = renounce_ownership(&nft, USERS[1]);
+    let event = NFTEvent::OwnershipTransferred {
+        previous_owner: USERS[1].into(),
+        new_owner: ZERO_ID.into(),
+    };
+    assert_ok(&res, USERS[1], event);
+    let res = grant_role(&nft, USERS[1], Role::Minter, USERS[1]);
+    assert_err(&res, USERS[1], NFTError::MissingRole(Role::Admin));
+}
 
+#[test]
+fn pause_and_unpause() {
+    let sys = System::new();
+    init_nft(&sys);
     let nft = sys.get_program(1);
-    let transaction_id: u64 = 0;
-    let res = add_minter(&nft, transaction_id, USERS[1].into(), USERS[0]);
-    assert!(!res.main_failed());
-    let res = add_minter(&nft, transaction_id + 1, USERS[2].into(), USERS[1]);
-    assert!(!res.main_failed());
+    let mut transaction_id: u64 = 0;
+    assert!(!mint(&nft, transaction_id, USERS[0]).main_failed());
 
-    let res = add_minter(&nft, transaction_id + 1, 5.into(), 7);
-    assert!(res.main_failed())
+    // must fail since the caller is neither the owner nor a pauser
+    let res = pause(&nft, USERS[1]);
+    assert_err(&res, USERS[1], NFTError::MissingRole(Role::Pauser));
+
+    assert!(!grant_role(&nft, USERS[0], Role::Pauser, USERS[1]).main_failed());
+    let res = pause(&nft, USERS[1]);
+    assert_ok(&res, USERS[1], NFTEvent::Paused);
+
+    transaction_id += 1;
+    let res = mint(&nft, transaction_id, USERS[0]);
+    assert_err(&res, USERS[0], NFTError::Paused);
+    let res = transfer(&nft, transaction_id, USERS[0], USERS[1], 0);
+    assert_err(&res, USERS[0], NFTError::Paused);
+    let res = approve(&nft, transaction_id, USERS[0], USERS[1], 0);
+    assert_err(&res, USERS[0], NFTError::Paused);
+    let res = burn(&nft, transaction_id, USERS[0], 0);
+    assert_err(&res, USERS[0], NFTError::Paused);
+
+    // read-only actions still work
+    let res = owner_of(&nft, USERS[1], 0);
+    let event = NFTEvent::Owner {
+        token_id: 0.into(),
+        owner: USERS[0].into(),
+    };
+    assert_ok(&res, USERS[1], event);
+
+    let res = unpause(&nft, USERS[0]);
+    assert_ok(&res, USERS[0], NFTEvent::Unpaused);
+
+    let res = transfer(&nft, transaction_id, USERS[0], USERS[1], 0);
+    let event = NFTEvent::Transfer(NFTTransfer {
+        from: USERS[0].into(),
+        to: USERS[1].into(),
+        token_id: 0.into(),
+    });
+    assert_ok(&res, USERS[0], event);
 }
 
 #[test]
@@ -170,13 +395,12 @@ fn burn_success() {
     assert!(!mint(&nft, transaction_id, USERS[0]).main_failed());
     transaction_id += 1;
     let res = burn(&nft, transaction_id, USERS[0], 0);
-    let message = NFTEvent::Transfer(NFTTransfer {
+    let event = NFTEvent::Transfer(NFTTransfer {
         from: USERS[0].into(),
         to: ZERO_ID.into(),
         token_id: 0.into(),
-    })
-    .encode();
-    assert!(res.contains(&(USERS[0], message)));
+    });
+    assert_ok(&res, USERS[0], event);
 }
 
 #[test]
@@ -188,10 +412,12 @@ fn burn_failures() {
     assert!(!mint(&nft, transaction_id, USERS[0]).main_failed());
     // must fail since the token doesn't exist
     transaction_id += 1;
-    assert!(burn(&nft, transaction_id, USERS[0], 1).main_failed());
+    let res = burn(&nft, transaction_id, USERS[0], 1);
+    assert_err(&res, USERS[0], NFTError::TokenDoesNotExist);
     // must fail since the caller is not the token owner
     transaction_id += 1;
-    assert!(burn(&nft, transaction_id, USERS[1], 0).main_failed());
+    let res = burn(&nft, transaction_id, USERS[1], 0);
+    assert_err(&res, USERS[1], NFTError::NotOwner);
 }
 
 #[test]
@@ -203,13 +429,12 @@ fn transfer_success() {
     assert!(!mint(&nft, transaction_id, USERS[0]).main_failed());
     transaction_id += 1;
     let res = transfer(&nft, transaction_id, USERS[0], USERS[1], 0);
-    let message = NFTEvent::Transfer(NFTTransfer {
+    let event = NFTEvent::Transfer(NFTTransfer {
         from: USERS[0].into(),
         to: USERS[1].into(),
         token_id: 0.into(),
-    })
-    .encode();
-    assert!(res.contains(&(USERS[0], message)));
+    });
+    assert_ok(&res, USERS[0], event);
 }
 
 #[test]
@@ -222,13 +447,16 @@ fn transfer_failures() {
 
     // must fail since the token doesn't exist
     transaction_id += 1;
-    assert!(transfer(&nft, transaction_id, USERS[0], USERS[1], 1).main_failed());
+    let res = transfer(&nft, transaction_id, USERS[0], USERS[1], 1);
+    assert_err(&res, USERS[0], NFTError::TokenDoesNotExist);
     // must fail since the caller is not the token owner
     transaction_id += 1;
-    assert!(transfer(&nft, transaction_id, USERS[1], USERS[0], 0).main_failed());
+    let res = transfer(&nft, transaction_id, USERS[1], USERS[0], 0);
+    assert_err(&res, USERS[1], NFTError::NotOwnerOrApproved);
     // must fail since transfer to the zero address
     transaction_id += 1;
-    assert!(transfer(&nft, transaction_id, USERS[1], ZERO_ID, 0).main_failed());
+    let res = transfer(&nft, transaction_id, USERS[0], ZERO_ID, 0);
+    assert_err(&res, USERS[0], NFTError::ZeroAddress);
 }
 
 #[test]
@@ -241,13 +469,12 @@ fn owner_success() {
     transaction_id += 1;
     assert!(!approve(&nft, transaction_id, USERS[0], USERS[1], 0).main_failed());
     let res = owner_of(&nft, USERS[1], 0);
-    println!("{:?}", res.decoded_log::<NFTEvent>());
-    let message = NFTEvent::Owner {
+    println!("{:?}", res.decoded_log::<Result<NFTEvent, NFTError>>());
+    let event = NFTEvent::Owner {
         token_id: 0.into(),
         owner: ActorId::from(USERS[0]),
-    }
-    .encode();
-    assert!(res.contains(&(USERS[1], message)));
+    };
+    assert_ok(&res, USERS[1], event);
 }
 
 #[test]
@@ -261,24 +488,22 @@ fn is_approved_to_success() {
     assert!(!approve(&nft, transaction_id, USERS[0], USERS[1], 0).main_failed());
 
     let res = is_approved_to(&nft, USERS[1], 0, USERS[1]);
-    println!("{:?}", res.decoded_log::<NFTEvent>());
-    let message = NFTEvent::IsApproved {
+    println!("{:?}", res.decoded_log::<Result<NFTEvent, NFTError>>());
+    let event = NFTEvent::IsApproved {
         to: USERS[1].into(),
         token_id: 0.into(),
         approved: true,
-    }
-    .encode();
-    assert!(res.contains(&(USERS[1], message)));
+    };
+    assert_ok(&res, USERS[1], event);
 
     let res = is_approved_to(&nft, USERS[1], 0, USERS[0]);
-    println!("{:?}", res.decoded_log::<NFTEvent>());
-    let message = NFTEvent::IsApproved {
+    println!("{:?}", res.decoded_log::<Result<NFTEvent, NFTError>>());
+    let event = NFTEvent::IsApproved {
         to: USERS[0].into(),
         token_id: 0.into(),
         approved: false,
-    }
-    .encode();
-    assert!(res.contains(&(USERS[1], message)));
+    };
+    assert_ok(&res, USERS[1], event);
 }
 
 #[test]
@@ -291,8 +516,7 @@ fn is_approved_to_failure() {
     transaction_id += 1;
     assert!(!approve(&nft, transaction_id, USERS[0], USERS[1], 0).main_failed());
     let res = is_approved_to(&nft, USERS[1], 1, USERS[1]);
-    println!("{:?}", res.decoded_log::<NFTEvent>());
-    assert!(res.main_failed());
+    assert_err(&res, USERS[1], NFTError::TokenDoesNotExist);
 }
 
 #[test]
@@ -304,13 +528,12 @@ fn approve_success() {
     assert!(!mint(&nft, transaction_id, USERS[0]).main_failed());
     transaction_id += 1;
     let res = approve(&nft, transaction_id, USERS[0], USERS[1], 0);
-    let message = NFTEvent::Approval(NFTApproval {
+    let event = NFTEvent::Approval(NFTApproval {
         owner: USERS[0].into(),
         approved_account: USERS[1].into(),
         token_id: 0.into(),
-    })
-    .encode();
-    assert!(res.contains(&(USERS[0], message)));
+    });
+    assert_ok(&res, USERS[0], event);
     transaction_id += 1;
     assert!(!transfer(&nft, transaction_id, USERS[1], USERS[2], 0).main_failed());
 }
@@ -324,13 +547,16 @@ fn approve_failures() {
     assert!(!mint(&nft, transaction_id, USERS[0]).main_failed());
     transaction_id += 1;
     // must fail since the token doesn't exist
-    assert!(approve(&nft, transaction_id, USERS[0], USERS[1], 1).main_failed());
+    let res = approve(&nft, transaction_id, USERS[0], USERS[1], 1);
+    assert_err(&res, USERS[0], NFTError::TokenDoesNotExist);
     transaction_id += 1;
     // must fail since the caller is not the token owner
-    assert!(approve(&nft, transaction_id, USERS[1], USERS[0], 0).main_failed());
+    let res = approve(&nft, transaction_id, USERS[1], USERS[0], 0);
+    assert_err(&res, USERS[1], NFTError::NotOwner);
     transaction_id += 1;
     // must fail since approval to the zero address
-    assert!(approve(&nft, transaction_id, USERS[1], ZERO_ID, 0).main_failed());
+    let res = approve(&nft, transaction_id, USERS[0], ZERO_ID, 0);
+    assert_err(&res, USERS[0], NFTError::ZeroAddress);
 
     //approve
     transaction_id += 1;
@@ -340,125 +566,354 @@ fn approve_failures() {
     assert!(!transfer(&nft, transaction_id, USERS[1], USERS[2], 0).main_failed());
     //must fail since approval was removed after transferring
     transaction_id += 1;
-    assert!(transfer(&nft, transaction_id, USERS[1], USERS[0], 0).main_failed());
+    let res = transfer(&nft, transaction_id, USERS[1], USERS[0], 0);
+    assert_err(&res, USERS[1], NFTError::NotOwnerOrApproved);
 }
 
 #[test]
-fn delegated_approve_success() {
+fn approval_expiration() {
     let sys = System::new();
     init_nft(&sys);
     let nft = sys.get_program(1);
-    let pair = Sr25519Pair::from_seed(&hex!(
-        "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60"
-    ));
-    let owner_id = pair.public().0;
-
     let mut transaction_id: u64 = 0;
-    assert!(!add_minter(&nft, transaction_id, owner_id.into(), USERS[0]).main_failed());
-    assert!(!mint_to_actor(&nft, transaction_id + 1, owner_id).main_failed());
+    assert!(!mint(&nft, transaction_id, USERS[0]).main_failed());
 
-    let message = DelegatedApproveMessage {
-        token_owner_id: owner_id.into(),
-        approved_actor_id: USERS[1].into(),
-        nft_program_id: 1.into(),
+    // must fail since the approval would expire at once
+    let expires_at = Some(sys.block_timestamp());
+    transaction_id += 1;
+    let res = approve_until(&nft, transaction_id, USERS[0], USERS[1], 0, expires_at);
+    assert_err(&res, USERS[0], NFTError::ApprovalExpired);
+    let expires_at = Some(sys.block_timestamp() + 10);
+    transaction_id += 1;
+    assert!(!approve_until(&nft, transaction_id, USERS[0], USERS[2], 0, expires_at).main_failed());
+
+    let res = is_approved_to(&nft, USERS[1], 0, USERS[1]);
+    let event = NFTEvent::IsApproved {
+        to: USERS[1].into(),
         token_id: 0.into(),
-        expiration_timestamp: sys.block_timestamp() + 10,
+        approved: false,
     };
-    let signature = pair.sign(message.encode().as_slice());
+    assert_ok(&res, USERS[1], event);
+    let res = transfer(&nft, transaction_id, USERS[1], USERS[1], 0);
+    assert_err(&res, USERS[1], NFTError::NotOwnerOrApproved);
+
+    sys.spend_blocks(1);
 
+    let res = transfer(&nft, transaction_id, USERS[2], USERS[2], 0);
+    assert_err(&res, USERS[2], NFTError::NotOwnerOrApproved);
+
+    // re-approving without expiration makes the approval permanent
     transaction_id += 1;
-    let res = delegated_approve(&nft, transaction_id, USERS[1], message, signature.0);
-    let message = NFTEvent::Approval(NFTApproval {
-        owner: owner_id.into(),
-        approved_account: USERS[1].into(),
+    assert!(!approve(&nft, transaction_id, USERS[0], USERS[2], 0).main_failed());
+    sys.spend_blocks(1);
+    let res = transfer(&nft, transaction_id, USERS[2], USERS[2], 0);
+    let event = NFTEvent::Transfer(NFTTransfer {
+        from: USERS[0].into(),
+        to: USERS[2].into(),
         token_id: 0.into(),
-    })
-    .encode();
-    assert!(res.contains(&(USERS[1], message)));
-    assert!(!transfer(&nft, transaction_id, USERS[1], USERS[2], 0).main_failed());
+    });
+    assert_ok(&res, USERS[2], event);
 }
 
 #[test]
-fn delegated_approve_failures() {
+fn operator_approval() {
     let sys = System::new();
     init_nft(&sys);
     let nft = sys.get_program(1);
-    let pair = Sr25519Pair::from_seed(&hex!(
-        "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60"
-    ));
-    let owner_id = pair.public().0;
-
     let mut transaction_id: u64 = 0;
-    assert!(!add_minter(&nft, transaction_id, owner_id.into(), USERS[0]).main_failed());
-    transaction_id += 1;
-    assert!(!mint_to_actor(&nft, transaction_id, owner_id).main_failed());
-    transaction_id += 1;
     assert!(!mint(&nft, transaction_id, USERS[0]).main_failed());
     transaction_id += 1;
-    assert!(!mint_to_actor(&nft, transaction_id, owner_id).main_failed());
+    assert!(!mint(&nft, transaction_id, USERS[0]).main_failed());
 
-    // Not owner can't approve nft
-    let message = DelegatedApproveMessage {
-        token_owner_id: owner_id.into(),
-        approved_actor_id: USERS[1].into(),
-        nft_program_id: 1.into(),
-        token_id: 1.into(),
-        expiration_timestamp: sys.block_timestamp() + 10,
+    let res = set_approval_for_all(&nft, USERS[0], USERS[1], true);
+    let event = NFTEvent::ApprovalForAll {
+        owner: USERS[0].into(),
+        operator: USERS[1].into(),
+        approved: true,
     };
-    let signature = pair.sign(message.encode().as_slice());
-    assert!(delegated_approve(&nft, transaction_id, USERS[1], message, signature.0).main_failed());
+    assert_ok(&res, USERS[0], event);
 
-    // Only approved actor in delegated approve can send delegated approve action
-    let message = DelegatedApproveMessage {
-        token_owner_id: owner_id.into(),
-        approved_actor_id: USERS[1].into(),
-        nft_program_id: 1.into(),
-        token_id: 0.into(),
-        expiration_timestamp: sys.block_timestamp() + 10,
+    let res = is_approved_for_all(&nft, USERS[2], USERS[0], USERS[1]);
+    let event = NFTEvent::IsApprovedForAll {
+        owner: USERS[0].into(),
+        operator: USERS[1].into(),
+        approved: true,
     };
-    let signature = pair.sign(message.encode().as_slice());
+    assert_ok(&res, USERS[2], event);
 
-    assert!(delegated_approve(&nft, transaction_id, USERS[0], message, signature.0).main_failed());
-    // Must fail if user tries to approve token in wrong contract
-    init_nft(&sys);
-    let second_nft = sys.get_program(2);
-    transaction_id += 1;
-    assert!(!add_minter(&second_nft, transaction_id, owner_id.into(), USERS[0]).main_failed());
     transaction_id += 1;
-    assert!(!mint_to_actor(&second_nft, transaction_id, owner_id).main_failed());
-
-    let message = DelegatedApproveMessage {
-        token_owner_id: owner_id.into(),
-        approved_actor_id: USERS[1].into(),
-        nft_program_id: 1.into(),
+    let res = transfer(&nft, transaction_id, USERS[1], USERS[2], 0);
+    let event = NFTEvent::Transfer(NFTTransfer {
+        from: USERS[0].into(),
+        to: USERS[2].into(),
         token_id: 0.into(),
-        expiration_timestamp: sys.block_timestamp() + 10,
-    };
-    let signature = pair.sign(message.encode().as_slice());
+    });
+    assert_ok(&res, USERS[1], event);
+    // operator approval doesn't extend to tokens of the new owner
+    transaction_id += 1;
+    let res = transfer(&nft, transaction_id, USERS[1], USERS[0], 0);
+    assert_err(&res, USERS[1], NFTError::NotOwnerOrApproved);
 
-    assert!(
-        delegated_approve(&second_nft, transaction_id, USERS[1], message, signature.0)
-            .main_failed()
-    );
+    let res = burn(&nft, transaction_id, USERS[1], 1);
+    let event = NFTEvent::Transfer(NFTTransfer {
+        from: USERS[0].into(),
+        to: ZERO_ID.into(),
+        token_id: 1.into(),
+    });
+    assert_ok(&res, USERS[1], event);
 
-    // Must fail if user tries to approve token to zero_id
-    let message = DelegatedApproveMessage {
-        token_owner_id: owner_id.into(),
-        approved_actor_id: 0.into(),
-        nft_program_id: 1.into(),
-        token_id: 0.into(),
-        expiration_timestamp: sys.block_timestamp() + 10,
-    };
-    let signature = pair.sign(message.encode().as_slice());
-    assert!(delegated_approve(&nft, transaction_id, 0, message, signature.0).main_failed());
+    assert!(!set_approval_for_all(&nft, USERS[0], USERS[1], false).main_failed());
+    transaction_id += 1;
+    assert!(!mint(&nft, transaction_id, USERS[0]).main_failed());
+    let res = transfer(&nft, transaction_id, USERS[1], USERS[2], 2);
+    assert_err(&res, USERS[1], NFTError::NotOwnerOrApproved);
+}
 
-    // Signature not corresponds to the message content
+#[test]
+fn revoke_approval_success() {
+    let sys = System::new();
+    init_nft(&sys);
+    let nft = sys.get_program(1);
+    let mut transaction_id: u64 = 0;
+    assert!(!mint(&nft, transaction_id, USERS[0]).main_failed());
+    transaction_id += 1;
+    assert!(!approve(&nft, transaction_id, USERS[0], USERS[1], 0).main_failed());
+    transaction_id += 1;
+    assert!(!approve(&nft, transaction_id, USERS[0], USERS[2], 0).main_failed());
+
+    // must fail since the caller is not the token owner
+    transaction_id += 1;
+    let res = revoke_approval(&nft, transaction_id, USERS[1], USERS[1], 0);
+    assert_err(&res, USERS[1], NFTError::NotOwner);
+
+    let res = revoke_approval(&nft, transaction_id, USERS[0], USERS[1], 0);
+    let event = NFTEvent::ApprovalRevoked(NFTApproval {
+        owner: USERS[0].into(),
+        approved_account: USERS[1].into(),
+        token_id: 0.into(),
+    });
+    assert_ok(&res, USERS[0], event);
+    let res = transfer(&nft, transaction_id, USERS[1], USERS[1], 0);
+    assert_err(&res, USERS[1], NFTError::NotOwnerOrApproved);
+
+    transaction_id += 1;
+    let res = clear_approvals(&nft, transaction_id, USERS[0], 0);
+    let event = NFTEvent::ApprovalsCleared {
+        owner: USERS[0].into(),
+        token_id: 0.into(),
+    };
+    assert_ok(&res, USERS[0], event);
+    let res = transfer(&nft, transaction_id, USERS[2], USERS[2], 0);
+    assert_err(&res, USERS[2], NFTError::NotOwnerOrApproved);
+}
+
+#[test]
+fn approvals_cleared_on_transfer() {
+    let sys = System::new();
+    init_nft(&sys);
+    let nft = sys.get_program(1);
+    let mut transaction_id: u64 = 0;
+    assert!(!mint(&nft, transaction_id, USERS[0]).main_failed());
+    transaction_id += 1;
+    assert!(!approve(&nft, transaction_id, USERS[0], USERS[1], 0).main_failed());
+    transaction_id += 1;
+    assert!(!transfer(&nft, transaction_id, USERS[0], USERS[2], 0).main_failed());
+
+    let res = is_approved_to(&nft, USERS[1], 0, USERS[1]);
+    let event = NFTEvent::IsApproved {
+        to: USERS[1].into(),
+        token_id: 0.into(),
+        approved: false,
+    };
+    assert_ok(&res, USERS[1], event);
+
+    // the previously approved account can't move the token anymore
+    let res = transfer(&nft, transaction_id, USERS[1], USERS[1], 0);
+    assert_err(&res, USERS[1], NFTError::NotOwnerOrApproved);
+    // even if the new owner returns it to the previous one
+    transaction_id += 1;
+    assert!(!transfer(&nft, transaction_id, USERS[2], USERS[0], 0).main_failed());
+    let res = transfer(&nft, transaction_id, USERS[1], USERS[1], 0);
+    assert_err(&res, USERS[1], NFTError::NotOwnerOrApproved);
+}
+
+#[test]
+fn delegated_approve_success() {
+    let sys = System::new();
+    init_nft(&sys);
+    let nft = sys.get_program(1);
+    let pair = Sr25519Pair::from_seed(&hex!(
+        "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60"
+    ));
+    let owner_id = pair.public().0;
+
+    let mut transaction_id: u64 = 0;
+    assert!(!add_minter(&nft, transaction_id, owner_id.into(), USERS[0]).main_failed());
+    assert!(!mint_to_actor(&nft, transaction_id + 1, owner_id).main_failed());
+
+    let message = DelegatedApproveMessage {
+        token_owner_id: owner_id.into(),
+        approved_actor_id: USERS[1].into(),
+        nft_program_id: 1.into(),
+        token_id: 0.into(),
+        expiration_timestamp: sys.block_timestamp() + 10,
+        nonce: 0,
+    };
+    let signature = pair.sign(message.encode().as_slice());
+
+    transaction_id += 1;
+    let res = delegated_approve(&nft, transaction_id, USERS[1], message, signature.0);
+    let event = NFTEvent::Approval(NFTApproval {
+        owner: owner_id.into(),
+        approved_account: USERS[1].into(),
+        token_id: 0.into(),
+    });
+    assert_ok(&res, USERS[1], event);
+    assert!(!transfer(&nft, transaction_id, USERS[1], USERS[2], 0).main_failed());
+}
+
+#[test]
+fn delegated_approve_replay() {
+    let sys = System::new();
+    init_nft(&sys);
+    let nft = sys.get_program(1);
+    let pair = Sr25519Pair::from_seed(&hex!(
+        "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60"
+    ));
+    let owner_id = pair.public().0;
+
+    let mut transaction_id: u64 = 0;
+    assert!(!add_minter(&nft, transaction_id, owner_id.into(), USERS[0]).main_failed());
+    assert!(!mint_to_actor(&nft, transaction_id + 1, owner_id).main_failed());
+
+    let message = DelegatedApproveMessage {
+        token_owner_id: owner_id.into(),
+        approved_actor_id: USERS[1].into(),
+        nft_program_id: 1.into(),
+        token_id: 0.into(),
+        expiration_timestamp: sys.block_timestamp() + 10,
+        nonce: 0,
+    };
+    let signature = pair.sign(message.encode().as_slice());
+
+    transaction_id += 1;
+    let res = delegated_approve(&nft, transaction_id, USERS[1], message.clone(), signature.0);
+    assert!(!res.main_failed());
+
+    // must fail since the signature was already used with another `transaction_id`
+    transaction_id += 1;
+    let res = delegated_approve(&nft, transaction_id, USERS[1], message, signature.0);
+    assert_err(&res, USERS[1], NFTError::InvalidNonce);
+
+    let message = DelegatedApproveMessage {
+        token_owner_id: owner_id.into(),
+        approved_actor_id: USERS[1].into(),
+        nft_program_id: 1.into(),
+        token_id: 0.into(),
+        expiration_timestamp: sys.block_timestamp() + 10,
+        nonce: 1,
+    };
+    let signature = pair.sign(message.encode().as_slice());
+
+    let res = increment_nonce(&nft, owner_id);
+    let event = NFTEvent::NonceIncremented {
+        owner: owner_id.into(),
+        nonce: 2,
+    };
+    assert!(res.contains(&(owner_id, Ok::<NFTEvent, NFTError>(event).encode())));
+
+    // must fail since the owner invalidated outstanding signatures
+    let res = delegated_approve(&nft, transaction_id, USERS[1], message, signature.0);
+    assert_err(&res, USERS[1], NFTError::InvalidNonce);
+}
+
+#[test]
+fn delegated_approve_failures() {
+    let sys = System::new();
+    init_nft(&sys);
+    let nft = sys.get_program(1);
+    let pair = Sr25519Pair::from_seed(&hex!(
+        "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60"
+    ));
+    let owner_id = pair.public().0;
+
+    let mut transaction_id: u64 = 0;
+    assert!(!add_minter(&nft, transaction_id, owner_id.into(), USERS[0]).main_failed());
+    transaction_id += 1;
+    assert!(!mint_to_actor(&nft, transaction_id, owner_id).main_failed());
+    transaction_id += 1;
+    assert!(!mint(&nft, transaction_id, USERS[0]).main_failed());
+    transaction_id += 1;
+    assert!(!mint_to_actor(&nft, transaction_id, owner_id).main_failed());
+
+    // Not owner can't approve nft
+    let message = DelegatedApproveMessage {
+        token_owner_id: owner_id.into(),
+        approved_actor_id: USERS[1].into(),
+        nft_program_id: 1.into(),
+        token_id: 1.into(),
+        expiration_timestamp: sys.block_timestamp() + 10,
+        nonce: 0,
+    };
+    let signature = pair.sign(message.encode().as_slice());
+    let res = delegated_approve(&nft, transaction_id, USERS[1], message, signature.0);
+    assert_err(&res, USERS[1], NFTError::WrongTokenOwner);
+
+    // Only approved actor in delegated approve can send delegated approve action
+    let message = DelegatedApproveMessage {
+        token_owner_id: owner_id.into(),
+        approved_actor_id: USERS[1].into(),
+        nft_program_id: 1.into(),
+        token_id: 0.into(),
+        expiration_timestamp: sys.block_timestamp() + 10,
+        nonce: 0,
+    };
+    let signature = pair.sign(message.encode().as_slice());
+
+    let res = delegated_approve(&nft, transaction_id, USERS[0], message, signature.0);
+    assert_err(&res, USERS[0], NFTError::NotApprovedActor);
+    // Must fail if user tries to approve token in wrong contract
+    init_nft(&sys);
+    let second_nft = sys.get_program(2);
+    transaction_id += 1;
+    assert!(!add_minter(&second_nft, transaction_id, owner_id.into(), USERS[0]).main_failed());
+    transaction_id += 1;
+    assert!(!mint_to_actor(&second_nft, transaction_id, owner_id).main_failed());
+
+    let message = DelegatedApproveMessage {
+        token_owner_id: owner_id.into(),
+        approved_actor_id: USERS[1].into(),
+        nft_program_id: 1.into(),
+        token_id: 0.into(),
+        expiration_timestamp: sys.block_timestamp() + 10,
+        nonce: 0,
+    };
+    let signature = pair.sign(message.encode().as_slice());
+
+    let res = delegated_approve(&second_nft, transaction_id, USERS[1], message, signature.0);
+    assert_err(&res, USERS[1], NFTError::WrongProgramId);
+
+    // Must fail if user tries to approve token to zero_id
+    let message = DelegatedApproveMessage {
+        token_owner_id: owner_id.into(),
+        approved_actor_id: 0.into(),
+        nft_program_id: 1.into(),
+        token_id: 0.into(),
+        expiration_timestamp: sys.block_timestamp() + 10,
+        nonce: 0,
+    };
+    let signature = pair.sign(message.encode().as_slice());
+    let res = delegated_approve(&nft, transaction_id, 0, message, signature.0);
+    assert_err(&res, 0, NFTError::ZeroAddress);
+
+    // Signature not corresponds to the message content
     let message = DelegatedApproveMessage {
         token_owner_id: owner_id.into(),
         approved_actor_id: USERS[1].into(),
         nft_program_id: 1.into(),
         token_id: 0.into(),
         expiration_timestamp: sys.block_timestamp() + 10,
+        nonce: 0,
     };
     let signature = pair.sign(message.encode().as_slice());
     let wrong_message = DelegatedApproveMessage {
@@ -467,10 +922,10 @@ fn delegated_approve_failures() {
         nft_program_id: 1.into(),
         token_id: 2.into(),
         expiration_timestamp: sys.block_timestamp() + 10,
+        nonce: 0,
     };
-    assert!(
-        delegated_approve(&nft, transaction_id, USERS[1], wrong_message, signature.0).main_failed()
-    );
+    let res = delegated_approve(&nft, transaction_id, USERS[1], wrong_message, signature.0);
+    assert_err(&res, USERS[1], NFTError::InvalidSignature);
 
     // Approve expired
     let message = DelegatedApproveMessage {
@@ -479,9 +934,838 @@ fn delegated_approve_failures() {
         nft_program_id: 1.into(),
         token_id: 0.into(),
         expiration_timestamp: sys.block_timestamp() + 10,
+        nonce: 0,
     };
     let signature = pair.sign(message.encode().as_slice());
 
     sys.spend_blocks(1);
-    assert!(delegated_approve(&nft, transaction_id, USERS[1], message, signature.0).main_failed());
+    let res = delegated_approve(&nft, transaction_id, USERS[1], message, signature.0);
+    assert_err(&res, USERS[1], NFTError::DelegationExpired);
+}
+
+#[test]
+fn delegated_transfer() {
+    let sys = System::new();
+    init_nft(&sys);
+    let nft = sys.get_program(1);
+    let pair = Sr25519Pair::from_seed(&hex!(
+        "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60"
+    ));
+    let owner_id = pair.public().0;
+
+    let mut transaction_id: u64 = 0;
+    assert!(!add_minter(&nft, transaction_id, owner_id.into(), USERS[0]).main_failed());
+    assert!(!mint_to_actor(&nft, transaction_id + 1, owner_id).main_failed());
+
+    let message = DelegatedTransferMessage {
+        token_owner_id: owner_id.into(),
+        to: USERS[2].into(),
+        nft_program_id: 1.into(),
+        token_id: 0.into(),
+        expiration_timestamp: sys.block_timestamp() + 10,
+        nonce: 0,
+    };
+    let signature = pair.sign(message.encode().as_slice());
+
+    // must fail since the message was signed for another recipient
+    transaction_id += 1;
+    let wrong_message = DelegatedTransferMessage {
+        to: USERS[1].into(),
+        ..message.clone()
+    };
+    let res = delegated_transfer(&nft, transaction_id, USERS[1], wrong_message, signature.0);
+    assert_err(&res, USERS[1], NFTError::InvalidSignature);
+
+    // any account can relay the signed transfer
+    transaction_id += 1;
+    let res = delegated_transfer(&nft, transaction_id, USERS[1], message.clone(), signature.0);
+    let event = NFTEvent::Transfer(NFTTransfer {
+        from: owner_id.into(),
+        to: USERS[2].into(),
+        token_id: 0.into(),
+    });
+    assert_ok(&res, USERS[1], event);
+
+    // must fail since the owner no longer holds the token
+    transaction_id += 1;
+    let res = delegated_transfer(&nft, transaction_id, USERS[1], message, signature.0);
+    assert_err(&res, USERS[1], NFTError::WrongTokenOwner);
+}
+
+#[test]
+fn batch_transfer() {
+    let sys = System::new();
+    init_nft(&sys);
+    let nft = sys.get_program(1);
+    assert!(!mint(&nft, 0, USERS[0]).main_failed());
+    assert!(!mint(&nft, 1, USERS[0]).main_failed());
+    assert!(!mint(&nft, 2, USERS[1]).main_failed());
+
+    // must fail since USERS[0] doesn't own the last token
+    let res = batch_transfer(&nft, 3, USERS[0], &[(USERS[2], 0), (USERS[2], 2)]);
+    assert_err(&res, USERS[0], NFTError::NotOwnerOrApproved);
+    // nothing was transferred
+    let res = owner_of(&nft, USERS[0], 0);
+    let event = NFTEvent::Owner {
+        token_id: 0.into(),
+        owner: USERS[0].into(),
+    };
+    assert_ok(&res, USERS[0], event);
+
+    // must fail since the same token is listed twice
+    let res = batch_transfer(&nft, 4, USERS[0], &[(USERS[1], 0), (USERS[2], 0)]);
+    assert_err(&res, USERS[0], NFTError::DuplicateToken);
+
+    let res = batch_transfer(&nft, 5, USERS[0], &[(USERS[1], 0), (USERS[2], 1)]);
+    let event = NFTEvent::BatchTransfer(vec![
+        NFTTransfer {
+            from: USERS[0].into(),
+            to: USERS[1].into(),
+            token_id: 0.into(),
+        },
+        NFTTransfer {
+            from: USERS[0].into(),
+            to: USERS[2].into(),
+            token_id: 1.into(),
+        },
+    ]);
+    assert_ok(&res, USERS[0], event.clone());
+
+    // the same transaction replies with the cached event
+    let res = batch_transfer(&nft, 5, USERS[0], &[(USERS[1], 0), (USERS[2], 1)]);
+    assert_ok(&res, USERS[0], event);
+}
+
+#[test]
+fn safe_transfer_to_program() {
+    let sys = System::new();
+    init_nft(&sys);
+    let nft = sys.get_program(1);
+    const ACCEPTING: u64 = 100;
+    const REJECTING: u64 = 101;
+    const SILENT: u64 = 102;
+    init_receiver(&sys, ACCEPTING, Some(true));
+    init_receiver(&sys, REJECTING, Some(false));
+    init_receiver(&sys, SILENT, None);
+    assert!(!mint(&nft, 0, USERS[0]).main_failed());
+    assert!(!mint(&nft, 1, USERS[0]).main_failed());
+    assert!(!approve(&nft, 2, USERS[0], USERS[1], 1).main_failed());
+
+    let res = safe_transfer(&nft, 3, USERS[0], ACCEPTING, 0, true);
+    let event = NFTEvent::Transfer(NFTTransfer {
+        from: USERS[0].into(),
+        to: ACCEPTING.into(),
+        token_id: 0.into(),
+    });
+    assert_ok(&res, USERS[0], event);
+
+    // must fail since the recipient rejects the token
+    let res = safe_transfer(&nft, 4, USERS[0], REJECTING, 1, true);
+    assert_err(&res, USERS[0], NFTError::TransferRejected);
+    let res = owner_of(&nft, USERS[0], 1);
+    let event = NFTEvent::Owner {
+        token_id: 1.into(),
+        owner: USERS[0].into(),
+    };
+    assert_ok(&res, USERS[0], event.clone());
+
+    // the rollback keeps the approval of USERS[1]
+    let res = safe_transfer(&nft, 0, USERS[1], SILENT, 1, true);
+    assert!(!res.main_failed());
+
+    // must fail while the token is awaiting the recipient's reply
+    let res = transfer(&nft, 5, USERS[0], USERS[2], 1);
+    assert_err(&res, USERS[0], NFTError::TokenPending);
+    assert!(!grant_role(&nft, USERS[0], Role::MetadataEditor, USERS[0]).main_failed());
+    let res = update_metadata(&nft, 6, USERS[0], 1, token_metadata());
+    assert_err(&res, USERS[0], NFTError::TokenPending);
+    // must fail since the transaction id is taken by the pending transfer
+    let res = approve(&nft, 0, USERS[1], USERS[2], 1);
+    assert_err(&res, USERS[1], NFTError::TransactionPending);
+
+    sys.spend_blocks(SAFE_TRANSFER_TIMEOUT);
+    let res = owner_of(&nft, USERS[0], 1);
+    assert_ok(&res, USERS[0], event);
+}
+
+#[test]
+fn safe_transfer_to_wallet() {
+    let sys = System::new();
+    init_nft(&sys);
+    let nft = sys.get_program(1);
+    assert!(!mint(&nft, 0, USERS[0]).main_failed());
+
+    // completes at once since no callback is awaited
+    let res = safe_transfer(&nft, 1, USERS[0], USERS[1], 0, false);
+    let event = NFTEvent::Transfer(NFTTransfer {
+        from: USERS[0].into(),
+        to: USERS[1].into(),
+        token_id: 0.into(),
+    });
+    assert_ok(&res, USERS[0], event.clone());
+    assert!(!transfer(&nft, 0, USERS[1], USERS[2], 0).main_failed());
+
+    // the same transaction replies with the cached event
+    let res = safe_transfer(&nft, 1, USERS[0], USERS[1], 0, false);
+    assert_ok(&res, USERS[0], event);
+}
+
+#[test]
+fn soulbound_tokens() {
+    let sys = System::new();
+    init_nft(&sys);
+    let nft = sys.get_program(1);
+    assert!(!mint_transferable(&nft, 0, USERS[0], Some(false)).main_failed());
+
+    let res = transfer(&nft, 1, USERS[0], USERS[1], 0);
+    assert_err(&res, USERS[0], NFTError::NonTransferable);
+    let res = approve(&nft, 2, USERS[0], USERS[1], 0);
+    assert_err(&res, USERS[0], NFTError::NonTransferable);
+
+    let state = state(&nft);
+    assert!(!metafns::token(state.clone(), 0.into()).transferable);
+    assert_eq!(
+        metafns::is_transferable(state.clone(), 0.into()),
+        Some(false)
+    );
+    assert_eq!(metafns::is_transferable(state, 1.into()), None);
+
+    let res = burn(&nft, 3, USERS[0], 0);
+    let event = NFTEvent::Transfer(NFTTransfer {
+        from: USERS[0].into(),
+        to: ZERO_ID.into(),
+        token_id: 0.into(),
+    });
+    assert_ok(&res, USERS[0], event);
+}
+
+#[test]
+fn soulbound_collection() {
+    let sys = System::new();
+    let mut config = init_config();
+    config.constraints.max_mint_count = None;
+    config.constraints.public_mint = true;
+    config.soulbound = true;
+    init_nft_with(&sys, config);
+    let nft = sys.get_program(1);
+
+    assert!(!mint(&nft, 0, USERS[0]).main_failed());
+    let res = transfer(&nft, 1, USERS[0], USERS[1], 0);
+    assert_err(&res, USERS[0], NFTError::NonTransferable);
+
+    // the flag can be overridden at mint
+    assert!(!mint_transferable(&nft, 2, USERS[0], Some(true)).main_failed());
+    let res = transfer(&nft, 3, USERS[0], USERS[1], 1);
+    let event = NFTEvent::Transfer(NFTTransfer {
+        from: USERS[0].into(),
+        to: USERS[1].into(),
+        token_id: 1.into(),
+    });
+    assert_ok(&res, USERS[0], event);
+
+    // and for every item of a batch
+    let items = [Some(true), None]
+        .into_iter()
+        .map(|transferable| MintItem {
+            to: USERS[0].into(),
+            token_metadata: token_metadata(),
+            transferable,
+            attributes: Vec::new(),
+        })
+        .collect();
+    assert!(!batch_mint_items(&nft, 4, USERS[0], items).main_failed());
+    let res = transfer(&nft, 5, USERS[0], USERS[1], 2);
+    let event = NFTEvent::Transfer(NFTTransfer {
+        from: USERS[0].into(),
+        to: USERS[1].into(),
+        token_id: 2.into(),
+    });
+    assert_ok(&res, USERS[0], event);
+    let res = transfer(&nft, 6, USERS[0], USERS[1], 3);
+    assert_err(&res, USERS[0], NFTError::NonTransferable);
+
+    // the override is ignored for public mints
+    assert!(!mint_transferable(&nft, 0, USERS[1], Some(true)).main_failed());
+    let res = transfer(&nft, 1, USERS[1], USERS[2], 4);
+    assert_err(&res, USERS[1], NFTError::NonTransferable);
+}
+
+#[test]
+fn token_locks() {
+    let sys = System::new();
+    init_nft(&sys);
+    let nft = sys.get_program(1);
+    assert!(!mint(&nft, 0, USERS[0]).main_failed());
+    assert!(!approve(&nft, 1, USERS[0], USERS[1], 0).main_failed());
+
+    // must fail since USERS[2] is neither the owner nor approved
+    let res = lock_token(&nft, USERS[2], 0, None);
+    assert_err(&res, USERS[2], NFTError::NotOwnerOrApproved);
+    // must fail since a per-token approval allows only locks with a deadline
+    let res = lock_token(&nft, USERS[1], 0, None);
+    assert_err(&res, USERS[1], NFTError::NotOwnerOrOperator);
+
+    assert!(!set_approval_for_all(&nft, USERS[0], USERS[1], true).main_failed());
+    let res = lock_token(&nft, USERS[1], 0, None);
+    let event = NFTEvent::TokenLocked {
+        token_id: 0.into(),
+        locker: USERS[1].into(),
+        until: None,
+    };
+    assert_ok(&res, USERS[1], event);
+
+    let res = is_locked(&nft, USERS[0], 0);
+    let event = NFTEvent::IsLocked {
+        token_id: 0.into(),
+        locked: true,
+    };
+    assert_ok(&res, USERS[0], event);
+
+    let res = transfer(&nft, 2, USERS[0], USERS[2], 0);
+    assert_err(&res, USERS[0], NFTError::TokenLocked);
+    let res = burn(&nft, 3, USERS[0], 0);
+    assert_err(&res, USERS[0], NFTError::TokenLocked);
+
+    // must fail since only the locker can lift or replace its lock
+    let res = lock_token(&nft, USERS[0], 0, Some(0));
+    assert_err(&res, USERS[0], NFTError::TokenLocked);
+    let res = unlock_token(&nft, USERS[0], 0);
+    assert_err(&res, USERS[0], NFTError::NotLocker);
+
+    let res = unlock_token(&nft, USERS[1], 0);
+    let event = NFTEvent::TokenUnlocked { token_id: 0.into() };
+    assert_ok(&res, USERS[1], event);
+    assert!(!transfer(&nft, 4, USERS[0], USERS[2], 0).main_failed());
+
+    // a lock with a deadline expires by itself
+    let until = sys.block_timestamp() + 10;
+    assert!(!lock_token(&nft, USERS[2], 0, Some(until)).main_failed());
+    let res = transfer(&nft, 0, USERS[2], USERS[0], 0);
+    assert_err(&res, USERS[2], NFTError::TokenLocked);
+
+    sys.spend_blocks(1);
+    let res = is_locked(&nft, USERS[2], 0);
+    let event = NFTEvent::IsLocked {
+        token_id: 0.into(),
+        locked: false,
+    };
+    assert_ok(&res, USERS[2], event);
+    assert!(!transfer(&nft, 1, USERS[2], USERS[0], 0).main_failed());
+
+    // a far deadline can't outlive the approval of the locker
+    assert!(!mint_to(&nft, 5, USERS[0], USERS[2]).main_failed());
+    assert!(!approve(&nft, 2, USERS[2], USERS[1], 1).main_failed());
+    assert!(!lock_token(&nft, USERS[1], 1, Some(u64::MAX)).main_failed());
+    let res = unlock_token(&nft, USERS[2], 1);
+    assert_err(&res, USERS[2], NFTError::NotLocker);
+
+    assert!(!revoke_approval(&nft, 3, USERS[2], USERS[1], 1).main_failed());
+    let res = unlock_token(&nft, USERS[2], 1);
+    let event = NFTEvent::TokenUnlocked { token_id: 1.into() };
+    assert_ok(&res, USERS[2], event);
+    assert!(!transfer(&nft, 4, USERS[2], USERS[0], 1).main_failed());
+}
+
+#[test]
+fn update_and_freeze_metadata() {
+    let sys = System::new();
+    init_nft(&sys);
+    let nft = sys.get_program(1);
+    assert!(!mint(&nft, 0, USERS[0]).main_failed());
+    assert!(!mint(&nft, 1, USERS[0]).main_failed());
+    let metadata = TokenMetadata {
+        name: "CryptoKitty".to_string(),
+        description: "Level 2".to_string(),
+        media: "http://".to_string(),
+        reference: "http://".to_string(),
+    };
+
+    // must fail since token owners can't update metadata in this collection
+    let res = update_metadata(&nft, 2, USERS[0], 0, metadata.clone());
+    assert_err(&res, USERS[0], NFTError::MissingRole(Role::MetadataEditor));
+
+    assert!(!grant_role(&nft, USERS[0], Role::MetadataEditor, USERS[1]).main_failed());
+    let res = update_metadata(&nft, 0, USERS[1], 0, metadata.clone());
+    let event = NFTEvent::MetadataUpdated {
+        token_id: 0.into(),
+        metadata: metadata.clone(),
+    };
+    assert_ok(&res, USERS[1], event);
+
+    let res = freeze_metadata(&nft, USERS[1], 0);
+    assert_ok(
+        &res,
+        USERS[1],
+        NFTEvent::MetadataFrozen { token_id: 0.into() },
+    );
+    let res = update_metadata(&nft, 1, USERS[1], 0, metadata.clone());
+    assert_err(&res, USERS[1], NFTError::MetadataFrozen);
+
+    // must fail since only the collection owner can freeze all metadata
+    let res = freeze_all_metadata(&nft, USERS[1]);
+    assert_err(&res, USERS[1], NFTError::NotCollectionOwner);
+    let res = freeze_all_metadata(&nft, USERS[0]);
+    assert_ok(&res, USERS[0], NFTEvent::AllMetadataFrozen);
+    let res = update_metadata(&nft, 2, USERS[1], 1, metadata);
+    assert_err(&res, USERS[1], NFTError::MetadataFrozen);
+}
+
+#[test]
+fn token_attributes() {
+    let sys = System::new();
+    let mut config = init_config();
+    config.constraints.public_mint = true;
+    init_nft_with(&sys, config);
+    let nft = sys.get_program(1);
+    let attributes = vec![
+        ("level".to_string(), AttributeValue::Number(1)),
+        ("rare".to_string(), AttributeValue::Bool(false)),
+    ];
+    assert!(!mint_with_attributes(&nft, 0, USERS[0], attributes).main_failed());
+
+    // must fail since USERS[0] isn't a metadata editor
+    let attributes = vec![("level".to_string(), AttributeValue::Number(2))];
+    let res = update_attributes(&nft, 1, USERS[0], 0, attributes.clone());
+    assert_err(&res, USERS[0], NFTError::MissingRole(Role::MetadataEditor));
+
+    assert!(!grant_role(&nft, USERS[0], Role::MetadataEditor, USERS[1]).main_failed());
+    // repeated keys are collapsed to the last value
+    let update = vec![
+        ("level".to_string(), AttributeValue::Number(3)),
+        ("level".to_string(), AttributeValue::Number(2)),
+    ];
+    let res = update_attributes(&nft, 0, USERS[1], 0, update);
+    let event = NFTEvent::AttributesUpdated {
+        token_id: 0.into(),
+        attributes,
+    };
+    assert_ok(&res, USERS[1], event);
+
+    assert!(!freeze_metadata(&nft, USERS[1], 0).main_failed());
+    let res = update_attributes(&nft, 1, USERS[1], 0, Vec::new());
+    assert_err(&res, USERS[1], NFTError::MetadataFrozen);
+
+    // attributes can be set for every item of a batch
+    let attributes = vec![("rare".to_string(), AttributeValue::Bool(true))];
+    let item = MintItem {
+        to: USERS[2].into(),
+        token_metadata: token_metadata(),
+        transferable: None,
+        attributes: attributes.clone(),
+    };
+    assert!(!batch_mint_items(&nft, 1, USERS[0], vec![item]).main_failed());
+    let attributes_of = |token_id: u64| {
+        state(&nft)
+            .attributes
+            .into_iter()
+            .find(|(id, _attributes)| *id == token_id.into())
+            .map(|(_id, attributes)| attributes)
+    };
+    assert_eq!(attributes_of(1), Some(attributes.clone()));
+
+    // the attributes are ignored for public mints
+    assert!(!mint_with_attributes(&nft, 0, USERS[2], attributes).main_failed());
+    assert_eq!(attributes_of(2), None);
+}
+
+#[test]
+fn set_base_uri_success() {
+    let sys = System::new();
+    let mut config = init_config();
+    config.collection.base_uri = String::from("https://example.com/");
+    init_nft_with(&sys, config);
+    let nft = sys.get_program(1);
+
+    let state = state(&nft);
+    assert_eq!(state.token.symbol, "MTK");
+    assert_eq!(state.token.base_uri, "https://example.com/");
+    assert_eq!(state.collection.base_uri, "https://example.com/");
+
+    let items = ["0.json", ""]
+        .into_iter()
+        .map(|reference| MintItem {
+            to: USERS[0].into(),
+            token_metadata: TokenMetadata {
+                reference: reference.to_string(),
+                ..token_metadata()
+            },
+            transferable: None,
+            attributes: Vec::new(),
+        })
+        .collect();
+    assert!(!batch_mint_items(&nft, 0, USERS[0], items).main_failed());
+
+    // must fail since only the collection owner can set the base URI
+    let res = set_base_uri(&nft, USERS[1], "ipfs://");
+    assert_err(&res, USERS[1], NFTError::NotCollectionOwner);
+
+    let res = set_base_uri(&nft, USERS[0], "ipfs://");
+    let event = NFTEvent::BaseUriSet {
+        base_uri: "ipfs://".to_string(),
+    };
+    assert_ok(&res, USERS[0], event);
+
+    let state = state(&nft);
+    assert_eq!(state.token.base_uri, "ipfs://");
+    assert_eq!(state.collection.base_uri, "ipfs://");
+    // the token reference is preferred to the token id
+    let token_uri = metafns::token_uri(state.clone(), 0.into());
+    assert_eq!(token_uri.as_deref(), Some("ipfs://0.json"));
+    let token_uri = metafns::token_uri(state.clone(), 1.into());
+    assert_eq!(token_uri.as_deref(), Some("ipfs://1"));
+    assert_eq!(metafns::token_uri(state, 2.into()), None);
+}
+
+#[test]
+fn update_collection_success() {
+    let sys = System::new();
+    init_nft(&sys);
+    let nft = sys.get_program(1);
+    let update = CollectionUpdate {
+        description: Some(String::from("Updated")),
+        logo_url: Some(String::from("ipfs://logo")),
+        socials: Some(vec![(
+            String::from("twitter"),
+            String::from("https://twitter.com/gear_techs"),
+        )]),
+        ..Default::default()
+    };
+
+    // must fail since only the collection owner can update it
+    let res = update_collection(&nft, USERS[1], update.clone());
+    assert_err(&res, USERS[1], NFTError::NotCollectionOwner);
+
+    let res = update_collection(&nft, USERS[0], update);
+    let event = NFTEvent::CollectionUpdated(Collection {
+        name: String::from("MyToken"),
+        description: String::from("Updated"),
+        symbol: String::from("MTK"),
+        base_uri: String::new(),
+        banner_url: String::new(),
+        logo_url: String::from("ipfs://logo"),
+        external_link: String::new(),
+        socials: vec![(
+            String::from("twitter"),
+            String::from("https://twitter.com/gear_techs"),
+        )],
+    });
+    assert_ok(&res, USERS[0], event);
+}
+
+#[test]
+fn public_mint() {
+    let sys = System::new();
+    let mut config = init_config();
+    config.constraints.max_mint_count = None;
+    config.constraints.public_mint = true;
+    config.constraints.price_tiers = vec![
+        PriceTier {
+            supply: 0,
+            price: 1000,
+        },
+        PriceTier {
+            supply: 1,
+            price: 2000,
+        },
+    ];
+    init_nft_with(&sys, config);
+    let nft = sys.get_program(1);
+    sys.mint_to(USERS[1], 10_000);
+
+    let res = mint_with_value(&nft, 0, USERS[1], 0);
+    assert_err(&res, USERS[1], NFTError::InsufficientValue);
+
+    // the overpayment is refunded
+    let res = mint_with_value(&nft, 1, USERS[1], 1500);
+    let event = NFTEvent::Transfer(NFTTransfer {
+        from: ZERO_ID.into(),
+        to: USERS[1].into(),
+        token_id: 0.into(),
+    });
+    assert_ok(&res, USERS[1], event);
+    sys.claim_value_from_mailbox(USERS[1]);
+    assert_eq!(sys.balance_of(USERS[1]), 9000);
+
+    // must fail since the second tier is more expensive; the value is refunded
+    let res = mint_with_value(&nft, 2, USERS[1], 1000);
+    assert_err(&res, USERS[1], NFTError::InsufficientValue);
+    sys.claim_value_from_mailbox(USERS[1]);
+    assert_eq!(sys.balance_of(USERS[1]), 9000);
+
+    assert!(!mint_with_value(&nft, 3, USERS[1], 2000).main_failed());
+    assert_eq!(sys.balance_of(USERS[1]), 7000);
+
+    // an overpayment below the existential deposit is kept for the sender
+    assert!(!mint_with_value(&nft, 4, USERS[1], 2100).main_failed());
+    sys.claim_value_from_mailbox(USERS[1]);
+    assert_eq!(sys.balance_of(USERS[1]), 4900);
+    assert_eq!(state(&nft).proceeds, 5000);
+    assert_eq!(state(&nft).refunds, vec![(USERS[1].into(), 100)]);
+
+    // must fail since the refund alone is below the existential deposit
+    let res = claim_refund(&nft, USERS[1], 0);
+    assert_err(&res, USERS[1], NFTError::WithdrawalFailed);
+
+    let res = claim_refund(&nft, USERS[1], 500);
+    assert_ok(&res, USERS[1], NFTEvent::RefundClaimed { amount: 600 });
+    sys.claim_value_from_mailbox(USERS[1]);
+    assert_eq!(sys.balance_of(USERS[1]), 5000);
+    assert!(state(&nft).refunds.is_empty());
+
+    // minters don't pay
+    assert!(!mint(&nft, 0, USERS[0]).main_failed());
+
+    let res = withdraw(&nft, USERS[1]);
+    assert_err(&res, USERS[1], NFTError::NotCollectionOwner);
+    let res = withdraw(&nft, USERS[0]);
+    assert_ok(&res, USERS[0], NFTEvent::Withdrawn { amount: 5000 });
+    sys.claim_value_from_mailbox(USERS[0]);
+    assert_eq!(sys.balance_of(USERS[0]), 5000);
+}
+
+#[test]
+fn mint_phases() {
+    let sys = System::new();
+    init_nft(&sys);
+    let nft = sys.get_program(1);
+    sys.mint_to(USERS[2], 10_000);
+    let now = sys.block_timestamp();
+    let phases = vec![
+        MintPhase {
+            start: now + 1,
+            end: now + 100_000,
+            price: 0,
+            max_per_wallet: Some(1),
+            eligibility: Eligibility::Allowlist(vec![USERS[1].into()]),
+        },
+        MintPhase {
+            start: now + 100_000,
+            end: now + 200_000,
+            price: 500,
+            max_per_wallet: None,
+            eligibility: Eligibility::Public,
+        },
+    ];
+
+    let res = set_upcoming_phases(&nft, USERS[1], phases.clone());
+    assert_err(&res, USERS[1], NFTError::NotCollectionOwner);
+
+    // must fail since the phases overlap
+    let overlapping = vec![phases[1].clone(), phases[0].clone()];
+    let res = set_upcoming_phases(&nft, USERS[0], overlapping);
+    assert_err(&res, USERS[0], NFTError::InvalidPhases);
+
+    let res = set_upcoming_phases(&nft, USERS[0], phases.clone());
+    let event = NFTEvent::UpcomingPhasesSet {
+        phases: phases.clone(),
+    };
+    assert_ok(&res, USERS[0], event);
+
+    // a rejected update keeps the schedule
+    let res = set_upcoming_phases(&nft, USERS[0], vec![phases[0].clone(); 2]);
+    assert_err(&res, USERS[0], NFTError::InvalidPhases);
+    assert_eq!(state(&nft).constraints.phases, phases);
+
+    // allowlist phase
+    let res = mint(&nft, 0, USERS[2]);
+    assert_err(&res, USERS[2], NFTError::NotEligible);
+    assert!(!mint(&nft, 0, USERS[1]).main_failed());
+    let res = mint(&nft, 1, USERS[1]);
+    assert_err(&res, USERS[1], NFTError::MintLimitReached);
+
+    // public phase
+    while sys.block_timestamp() < now + 100_000 {
+        sys.spend_blocks(1);
+    }
+    let res = mint(&nft, 1, USERS[2]);
+    assert_err(&res, USERS[2], NFTError::InsufficientValue);
+    assert!(!mint_with_value(&nft, 2, USERS[2], 500).main_failed());
+
+    while sys.block_timestamp() < now + 200_000 {
+        sys.spend_blocks(1);
+    }
+    let res = mint_with_value(&nft, 3, USERS[2], 500);
+    assert_err(&res, USERS[2], NFTError::MintClosed);
+    // minters aren't bound by the phases
+    assert!(!mint(&nft, 0, USERS[0]).main_failed());
+}
+
+#[test]
+fn invalid_init_phases() {
+    let sys = System::new();
+    sys.init_logger();
+    let nft = gtest::Program::current(&sys);
+    let phase = MintPhase {
+        start: 10,
+        end: 20,
+        ..Default::default()
+    };
+    let mut config = init_config();
+    config.constraints.phases = vec![phase.clone(), phase];
+
+    assert!(nft.send(USERS[0], config).main_failed());
+}
+
+#[test]
+fn merkle_allowlist() {
+    let sys = System::new();
+    init_nft(&sys);
+    let nft = sys.get_program(1);
+    let allowlist: &[(u64, u32)] = &[(USERS[1], 2), (USERS[2], 1), (6, 5)];
+    let tree = merkle::MerkleTree::new(
+        allowlist
+            .iter()
+            .map(|(account, allocation)| merkle::leaf(&(*account).into(), *allocation))
+            .collect(),
+    );
+    let proof = AllowlistProof {
+        allocation: 2,
+        proof: tree.proof(0).expect("Leaf must exist"),
+    };
+
+    // must fail since the allowlist isn't set yet
+    let res = mint_allowlisted(&nft, 0, USERS[1], proof.clone());
+    assert_err(&res, USERS[1], NFTError::InvalidProof);
+
+    let res = set_merkle_root(&nft, USERS[1], Some(tree.root()));
+    assert_err(&res, USERS[1], NFTError::NotCollectionOwner);
+    let res = set_merkle_root(&nft, USERS[0], Some(tree.root()));
+    let event = NFTEvent::MerkleRootSet {
+        root: Some(tree.root()),
+    };
+    assert_ok(&res, USERS[0], event);
+
+    // must fail since the allocation doesn't match the leaf
+    let wrong_proof = AllowlistProof {
+        allocation: 5,
+        ..proof.clone()
+    };
+    let res = mint_allowlisted(&nft, 0, USERS[1], wrong_proof);
+    assert_err(&res, USERS[1], NFTError::InvalidProof);
+    // must fail since the proof belongs to another account
+    let res = mint_allowlisted(&nft, 0, USERS[2], proof.clone());
+    assert_err(&res, USERS[2], NFTError::InvalidProof);
+
+    assert!(!mint_allowlisted(&nft, 0, USERS[1], proof.clone()).main_failed());
+    assert!(!mint_allowlisted(&nft, 1, USERS[1], proof.clone()).main_failed());
+    let res = mint_allowlisted(&nft, 2, USERS[1], proof);
+    assert_err(&res, USERS[1], NFTError::MintLimitReached);
+
+    let proof = AllowlistProof {
+        allocation: 1,
+        proof: tree.proof(1).expect("Leaf must exist"),
+    };
+    assert!(!mint_allowlisted(&nft, 0, USERS[2], proof).main_failed());
+    let res = mint(&nft, 1, USERS[2]);
+    assert_err(&res, USERS[2], NFTError::NotAuthorizedMinter);
+}
+
+#[test]
+fn max_per_wallet() {
+    let sys = System::new();
+    let mut config = init_config();
+    config.constraints.max_mint_count = None;
+    config.constraints.public_mint = true;
+    config.constraints.max_per_wallet = Some(2);
+    init_nft_with(&sys, config);
+    let nft = sys.get_program(1);
+
+    assert!(!mint(&nft, 0, USERS[1]).main_failed());
+    assert!(!mint(&nft, 1, USERS[1]).main_failed());
+
+    // must fail even after giving a token away since mints are counted
+    assert!(!transfer(&nft, 2, USERS[1], USERS[2], 0).main_failed());
+    let res = mint(&nft, 3, USERS[1]);
+    assert_err(&res, USERS[1], NFTError::MintLimitReached);
+
+    // minters aren't limited
+    for transaction_id in 0..3 {
+        assert!(!mint(&nft, transaction_id, USERS[0]).main_failed());
+    }
+}
+
+#[test]
+fn redeem_mint_voucher() {
+    let sys = System::new();
+    let mut config = init_config();
+    config.constraints.max_per_wallet = Some(2);
+    init_nft_with(&sys, config);
+    let nft = sys.get_program(1);
+    let pair = Sr25519Pair::from_seed(&hex!(
+        "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60"
+    ));
+    let minter_id = pair.public().0;
+    sys.mint_to(USERS[1], 10_000);
+
+    let voucher = MintVoucher {
+        minter: minter_id.into(),
+        to: None,
+        token_metadata: token_metadata(),
+        transferable: None,
+        attributes: Vec::new(),
+        price: 1000,
+        nft_program_id: 1.into(),
+        expiration_timestamp: sys.block_timestamp() + 100_000,
+        nonce: 7,
+    };
+    let signature = pair.sign(voucher.encode().as_slice());
+
+    // must fail since the signer isn't a minter yet
+    let res = redeem_voucher(&nft, USERS[1], voucher.clone(), signature.0, 1000);
+    assert_err(&res, USERS[1], NFTError::NotAuthorizedMinter);
+
+    assert!(!add_minter(&nft, 0, minter_id.into(), USERS[0]).main_failed());
+    let res = redeem_voucher(&nft, USERS[1], voucher.clone(), signature.0, 500);
+    assert_err(&res, USERS[1], NFTError::InsufficientValue);
+
+    let res = redeem_voucher(&nft, USERS[1], voucher.clone(), signature.0, 1000);
+    let event = NFTEvent::Transfer(NFTTransfer {
+        from: ZERO_ID.into(),
+        to: USERS[1].into(),
+        token_id: 0.into(),
+    });
+    assert_ok(&res, USERS[1], event);
+    sys.claim_value_from_mailbox(USERS[1]);
+    assert_eq!(sys.balance_of(USERS[1]), 9000);
+
+    let res = redeem_voucher(&nft, USERS[1], voucher.clone(), signature.0, 1000);
+    assert_err(&res, USERS[1], NFTError::VoucherUsed);
+
+    // must fail since the price was changed after signing
+    let tampered = MintVoucher {
+        price: 0,
+        nonce: 8,
+        ..voucher.clone()
+    };
+    let res = redeem_voucher(&nft, USERS[1], tampered, signature.0, 0);
+    assert_err(&res, USERS[1], NFTError::InvalidSignature);
+
+    // the token goes to the recipient set by the minter
+    let attributes = vec![("level".to_string(), AttributeValue::Number(1))];
+    let voucher = MintVoucher {
+        to: Some(USERS[2].into()),
+        attributes: attributes.clone(),
+        price: 0,
+        nonce: 8,
+        ..voucher
+    };
+    let signature = pair.sign(voucher.encode().as_slice());
+    let res = redeem_voucher(&nft, USERS[1], voucher.clone(), signature.0, 0);
+    let event = NFTEvent::Transfer(NFTTransfer {
+        from: ZERO_ID.into(),
+        to: USERS[2].into(),
+        token_id: 1.into(),
+    });
+    assert_ok(&res, USERS[1], event);
+    let state = state(&nft);
+    assert!(state.attributes.contains(&(1.into(), attributes)));
+
+    // must fail since redemptions count towards the per-wallet limit
+    let voucher = MintVoucher {
+        nonce: 9,
+        ..voucher
+    };
+    let signature = pair.sign(voucher.encode().as_slice());
+    let res = redeem_voucher(&nft, USERS[1], voucher, signature.0, 0);
+    assert_err(&res, USERS[1], NFTError::MintLimitReached);
 }
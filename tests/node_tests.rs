@@ -16,6 +16,8 @@ async fn mint_test() -> Result<()> {
     let collection = Collection {
         name: String::from("MyToken"),
         description: String::from("My token"),
+        symbol: String::from("MTK"),
+        ..Default::default()
     };
     let actor_id = ActorId::from_slice(&api.account_id().encode()).unwrap();
     let init_nft = InitNFT {
@@ -24,7 +26,9 @@ async fn mint_test() -> Result<()> {
         constraints: Constraints {
             max_mint_count: Some(100),
             authorized_minters: vec![actor_id],
+            ..Default::default()
         },
+        ..Default::default()
     }
     .encode();
     let gas_info = api
@@ -55,6 +59,9 @@ async fn mint_test() -> Result<()> {
     let mint_payload = NFTAction::Mint {
         transaction_id,
         token_metadata,
+        transferable: None,
+        attributes: Vec::new(),
+        allowlist_proof: None,
     };
 
     let gas_info = api
@@ -84,6 +91,8 @@ async fn burn_test() -> Result<()> {
     let collection = Collection {
         name: String::from("MyToken"),
         description: String::from("My token"),
+        symbol: String::from("MTK"),
+        ..Default::default()
     };
 
     let actor_id = ActorId::from_slice(&api.account_id().encode()).unwrap();
@@ -93,7 +102,9 @@ async fn burn_test() -> Result<()> {
         constraints: Constraints {
             max_mint_count: Some(100),
             authorized_minters: vec![actor_id],
+            ..Default::default()
         },
+        ..Default::default()
     }
     .encode();
     let gas_info = api
@@ -124,6 +135,9 @@ async fn burn_test() -> Result<()> {
     let mint_payload = NFTAction::Mint {
         transaction_id,
         token_metadata,
+        transferable: None,
+        attributes: Vec::new(),
+        allowlist_proof: None,
     };
 
     let gas_info = api
@@ -191,6 +205,8 @@ async fn transfer_test() -> Result<()> {
     let collection = Collection {
         name: String::from("MyToken"),
         description: String::from("My token"),
+        symbol: String::from("MTK"),
+        ..Default::default()
     };
 
     let actor_id = ActorId::from_slice(&api.account_id().encode()).unwrap();
@@ -200,7 +216,9 @@ async fn transfer_test() -> Result<()> {
         constraints: Constraints {
             max_mint_count: Some(100),
             authorized_minters: vec![actor_id],
+            ..Default::default()
         },
+        ..Default::default()
     }
     .encode();
     let gas_info = api
@@ -231,6 +249,9 @@ async fn transfer_test() -> Result<()> {
     let mint_payload = NFTAction::Mint {
         transaction_id,
         token_metadata,
+        transferable: None,
+        attributes: Vec::new(),
+        allowlist_proof: None,
     };
 
     let gas_info = api
@@ -281,6 +302,8 @@ async fn owner_test() -> Result<()> {
     let collection = Collection {
         name: String::from("MyToken"),
         description: String::from("My token"),
+        symbol: String::from("MTK"),
+        ..Default::default()
     };
 
     let actor_id = ActorId::from_slice(&api.account_id().encode()).unwrap();
@@ -290,7 +313,9 @@ async fn owner_test() -> Result<()> {
         constraints: Constraints {
             max_mint_count: Some(100),
             authorized_minters: vec![actor_id],
+            ..Default::default()
         },
+        ..Default::default()
     }
     .encode();
     let gas_info = api
@@ -321,6 +346,9 @@ async fn owner_test() -> Result<()> {
     let mint_payload = NFTAction::Mint {
         transaction_id,
         token_metadata,
+        transferable: None,
+        attributes: Vec::new(),
+        allowlist_proof: None,
     };
 
     let gas_info = api
@@ -365,6 +393,8 @@ async fn approved() -> Result<()> {
     let collection = Collection {
         name: String::from("MyToken"),
         description: String::from("My token"),
+        symbol: String::from("MTK"),
+        ..Default::default()
     };
 
     let actor_id = ActorId::from_slice(&api.account_id().encode()).unwrap();
@@ -374,7 +404,9 @@ async fn approved() -> Result<()> {
         constraints: Constraints {
             max_mint_count: Some(100),
             authorized_minters: vec![actor_id],
+            ..Default::default()
         },
+        ..Default::default()
     }
     .encode();
     let gas_info = api
@@ -405,6 +437,9 @@ async fn approved() -> Result<()> {
     let mint_payload = NFTAction::Mint {
         transaction_id,
         token_metadata,
+        transferable: None,
+        attributes: Vec::new(),
+        allowlist_proof: None,
     };
 
     let gas_info = api
@@ -424,6 +459,7 @@ async fn approved() -> Result<()> {
         transaction_id,
         to: ActorId::from(3),
         token_id: 0.into(),
+        expires_at: None,
     };
 
     let gas_info = api
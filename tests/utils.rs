@@ -1,44 +1,224 @@
 use gear_lib::non_fungible_token::token::*;
-use gstd::ActorId;
-use gtest::{Program, RunResult, System};
+use gstd::{ActorId, Encode};
+use gtest::{Program, RunResult, System, WasmProgram};
 use nft_io::*;
 
 const USERS: &[u64] = &[3, 4, 5];
 
-pub fn init_nft(sys: &System) {
-    sys.init_logger();
-    let nft = Program::current(sys);
-
-    let collection = Collection {
-        name: String::from("MyToken"),
-        description: String::from("My token"),
-    };
-
-    let init_nft = InitNFT {
-        collection,
-        royalties: None,
+/// Returns the config deployed by [`init_nft`] for tests to override.
+pub fn init_config() -> InitNFT {
+    InitNFT {
+        collection: Collection {
+            name: String::from("MyToken"),
+            description: String::from("My token"),
+            symbol: String::from("MTK"),
+            ..Default::default()
+        },
         constraints: Constraints {
             max_mint_count: Some(100),
             authorized_minters: vec![USERS[0].into()],
+            ..Default::default()
         },
-    };
+        ..Default::default()
+    }
+}
 
-    let res = nft.send(USERS[0], init_nft);
+pub fn init_nft(sys: &System) {
+    init_nft_with(sys, init_config());
+}
+
+pub fn init_nft_with(sys: &System, config: InitNFT) {
+    sys.init_logger();
+    let nft = Program::current(sys);
+
+    let res = nft.send(USERS[0], config);
 
     assert!(!res.main_failed());
 }
 
+/// Replies to [`NFTReceiverAction::OnNftReceived`] with `accept` or doesn't
+/// reply at all if it's `None`.
+#[derive(Debug)]
+struct Receiver {
+    accept: Option<bool>,
+}
+
+impl WasmProgram for Receiver {
+    fn init(&mut self, _payload: Vec<u8>) -> Result<Option<Vec<u8>>, &'static str> {
+        Ok(None)
+    }
+
+    fn handle(&mut self, _payload: Vec<u8>) -> Result<Option<Vec<u8>>, &'static str> {
+        Ok(self.accept.map(|accept| accept.encode()))
+    }
+
+    fn handle_reply(&mut self, _payload: Vec<u8>) -> Result<(), &'static str> {
+        Ok(())
+    }
+
+    fn handle_signal(&mut self, _payload: Vec<u8>) -> Result<(), &'static str> {
+        Ok(())
+    }
+
+    fn state(&mut self) -> Result<Vec<u8>, &'static str> {
+        Ok(Vec::new())
+    }
+}
+
+pub fn init_receiver(sys: &System, id: u64, accept: Option<bool>) -> Program {
+    let receiver = Program::mock_with_id(sys, id, Receiver { accept });
+    assert!(!receiver.send_bytes(USERS[0], []).main_failed());
+    receiver
+}
+
+pub fn assert_ok(res: &RunResult, member: u64, event: NFTEvent) {
+    assert!(res.contains(&(member, Ok::<NFTEvent, NFTError>(event).encode())));
+}
+
+pub fn assert_err(res: &RunResult, member: u64, error: NFTError) {
+    assert!(res.contains(&(member, Err::<NFTEvent, NFTError>(error).encode())));
+}
+
+pub fn state(nft: &Program) -> IoNFT {
+    nft.read_state().expect("Failed to read the state")
+}
+
+pub fn token_metadata() -> TokenMetadata {
+    TokenMetadata {
+        name: "CryptoKitty".to_string(),
+        description: "Description".to_string(),
+        media: "http://".to_string(),
+        reference: "http://".to_string(),
+    }
+}
+
 pub fn mint(nft: &Program, transaction_id: u64, member: u64) -> RunResult {
+    mint_transferable(nft, transaction_id, member, None)
+}
+
+pub fn mint_transferable(
+    nft: &Program,
+    transaction_id: u64,
+    member: u64,
+    transferable: Option<bool>,
+) -> RunResult {
+    nft.send(
+        member,
+        NFTAction::Mint {
+            transaction_id,
+            token_metadata: token_metadata(),
+            transferable,
+            attributes: Vec::new(),
+            allowlist_proof: None,
+        },
+    )
+}
+
+pub fn mint_with_value(nft: &Program, transaction_id: u64, member: u64, value: u128) -> RunResult {
+    nft.send_with_value(
+        member,
+        NFTAction::Mint {
+            transaction_id,
+            token_metadata: token_metadata(),
+            transferable: None,
+            attributes: Vec::new(),
+            allowlist_proof: None,
+        },
+        value,
+    )
+}
+
+pub fn mint_with_attributes(
+    nft: &Program,
+    transaction_id: u64,
+    member: u64,
+    attributes: Attributes,
+) -> RunResult {
     nft.send(
         member,
         NFTAction::Mint {
             transaction_id,
-            token_metadata: TokenMetadata {
-                name: "CryptoKitty".to_string(),
-                description: "Description".to_string(),
-                media: "http://".to_string(),
-                reference: "http://".to_string(),
-            },
+            token_metadata: token_metadata(),
+            transferable: None,
+            attributes,
+            allowlist_proof: None,
+        },
+    )
+}
+
+pub fn mint_allowlisted(
+    nft: &Program,
+    transaction_id: u64,
+    member: u64,
+    allowlist_proof: AllowlistProof,
+) -> RunResult {
+    nft.send(
+        member,
+        NFTAction::Mint {
+            transaction_id,
+            token_metadata: token_metadata(),
+            transferable: None,
+            attributes: Vec::new(),
+            allowlist_proof: Some(allowlist_proof),
+        },
+    )
+}
+
+pub fn mint_to(nft: &Program, transaction_id: u64, member: u64, to: u64) -> RunResult {
+    nft.send(
+        member,
+        NFTAction::MintTo {
+            transaction_id,
+            to: to.into(),
+            token_metadata: token_metadata(),
+            transferable: None,
+            attributes: Vec::new(),
+        },
+    )
+}
+
+pub fn batch_mint(nft: &Program, transaction_id: u64, member: u64, to: &[u64]) -> RunResult {
+    let items = to
+        .iter()
+        .map(|to| MintItem {
+            to: (*to).into(),
+            token_metadata: token_metadata(),
+            transferable: None,
+            attributes: Vec::new(),
+        })
+        .collect();
+    batch_mint_items(nft, transaction_id, member, items)
+}
+
+pub fn batch_mint_items(
+    nft: &Program,
+    transaction_id: u64,
+    member: u64,
+    items: Vec<MintItem>,
+) -> RunResult {
+    nft.send(
+        member,
+        NFTAction::BatchMint {
+            transaction_id,
+            items,
+        },
+    )
+}
+
+pub fn batch_transfer(
+    nft: &Program,
+    transaction_id: u64,
+    from: u64,
+    transfers: &[(u64, u64)],
+) -> RunResult {
+    nft.send(
+        from,
+        NFTAction::BatchTransfer {
+            transaction_id,
+            transfers: transfers
+                .iter()
+                .map(|(to, token_id)| ((*to).into(), (*token_id).into()))
+                .collect(),
         },
     )
 }
@@ -58,6 +238,80 @@ pub fn add_minter(
     )
 }
 
+pub fn remove_minter(
+    nft: &Program,
+    transaction_id: u64,
+    minter_id: ActorId,
+    member: u64,
+) -> RunResult {
+    nft.send(
+        member,
+        NFTAction::RemoveMinter {
+            transaction_id,
+            minter_id,
+        },
+    )
+}
+
+pub fn set_minters(nft: &Program, transaction_id: u64, minters: &[u64], member: u64) -> RunResult {
+    nft.send(
+        member,
+        NFTAction::SetMinters {
+            transaction_id,
+            minters: minters.iter().map(|minter| (*minter).into()).collect(),
+        },
+    )
+}
+
+pub fn grant_role(nft: &Program, from: u64, role: Role, account: u64) -> RunResult {
+    nft.send(
+        from,
+        NFTAction::GrantRole {
+            role,
+            account: account.into(),
+        },
+    )
+}
+
+pub fn revoke_role(nft: &Program, from: u64, role: Role, account: u64) -> RunResult {
+    nft.send(
+        from,
+        NFTAction::RevokeRole {
+            role,
+            account: account.into(),
+        },
+    )
+}
+
+pub fn renounce_role(nft: &Program, from: u64, role: Role) -> RunResult {
+    nft.send(from, NFTAction::RenounceRole { role })
+}
+
+pub fn transfer_ownership(nft: &Program, from: u64, new_owner: u64) -> RunResult {
+    nft.send(
+        from,
+        NFTAction::TransferOwnership {
+            new_owner: new_owner.into(),
+        },
+    )
+}
+
+pub fn accept_ownership(nft: &Program, from: u64) -> RunResult {
+    nft.send(from, NFTAction::AcceptOwnership)
+}
+
+pub fn renounce_ownership(nft: &Program, from: u64) -> RunResult {
+    nft.send(from, NFTAction::RenounceOwnership)
+}
+
+pub fn pause(nft: &Program, from: u64) -> RunResult {
+    nft.send(from, NFTAction::Pause)
+}
+
+pub fn unpause(nft: &Program, from: u64) -> RunResult {
+    nft.send(from, NFTAction::Unpause)
+}
+
 pub fn burn(nft: &Program, transaction_id: u64, member: u64, token_id: u64) -> RunResult {
     nft.send(
         member,
@@ -68,6 +322,26 @@ pub fn burn(nft: &Program, transaction_id: u64, member: u64, token_id: u64) -> R
     )
 }
 
+pub fn safe_transfer(
+    nft: &Program,
+    transaction_id: u64,
+    from: u64,
+    to: u64,
+    token_id: u64,
+    require_callback: bool,
+) -> RunResult {
+    nft.send(
+        from,
+        NFTAction::SafeTransfer {
+            transaction_id,
+            to: to.into(),
+            token_id: token_id.into(),
+            data: Vec::new(),
+            require_callback,
+        },
+    )
+}
+
 pub fn transfer(
     nft: &Program,
     transaction_id: u64,
@@ -104,13 +378,76 @@ pub fn is_approved_to(nft: &Program, from: u64, token_id: u64, to: u64) -> RunRe
     )
 }
 
+pub fn set_approval_for_all(nft: &Program, from: u64, operator: u64, approved: bool) -> RunResult {
+    nft.send(
+        from,
+        NFTAction::SetApprovalForAll {
+            operator: operator.into(),
+            approved,
+        },
+    )
+}
+
+pub fn is_approved_for_all(nft: &Program, from: u64, owner: u64, operator: u64) -> RunResult {
+    nft.send(
+        from,
+        NFTAction::IsApprovedForAll {
+            owner: owner.into(),
+            operator: operator.into(),
+        },
+    )
+}
+
 pub fn approve(nft: &Program, transaction_id: u64, from: u64, to: u64, token_id: u64) -> RunResult {
+    approve_until(nft, transaction_id, from, to, token_id, None)
+}
+
+pub fn approve_until(
+    nft: &Program,
+    transaction_id: u64,
+    from: u64,
+    to: u64,
+    token_id: u64,
+    expires_at: Option<u64>,
+) -> RunResult {
     nft.send(
         from,
         NFTAction::Approve {
             transaction_id,
             to: to.into(),
             token_id: token_id.into(),
+            expires_at,
+        },
+    )
+}
+
+pub fn is_transaction_cached(nft: &Program, from: u64, transaction_hash: H256) -> RunResult {
+    nft.send(from, NFTAction::IsTransactionCached { transaction_hash })
+}
+
+pub fn revoke_approval(
+    nft: &Program,
+    transaction_id: u64,
+    from: u64,
+    to: u64,
+    token_id: u64,
+) -> RunResult {
+    nft.send(
+        from,
+        NFTAction::RevokeApproval {
+            transaction_id,
+            to: to.into(),
+            token_id: token_id.into(),
+        },
+    )
+}
+
+pub fn clear_approvals(nft: &Program, transaction_id: u64, from: u64, token_id: u64) -> RunResult {
+    nft.send(
+        from,
+        NFTAction::ClearApprovals {
+            transaction_id,
+            token_id: token_id.into(),
         },
     )
 }
@@ -130,17 +467,148 @@ pub fn delegated_approve(
     nft.send(from, action)
 }
 
+pub fn delegated_transfer(
+    nft: &Program,
+    transaction_id: u64,
+    from: u64,
+    message: DelegatedTransferMessage,
+    signature: [u8; 64],
+) -> RunResult {
+    let action = NFTAction::DelegatedTransfer {
+        transaction_id,
+        message,
+        signature,
+    };
+    nft.send(from, action)
+}
+
+pub fn increment_nonce(nft: &Program, from: [u8; 32]) -> RunResult {
+    nft.send(from, NFTAction::IncrementNonce)
+}
+
+pub fn lock_token(nft: &Program, from: u64, token_id: u64, until: Option<u64>) -> RunResult {
+    nft.send(
+        from,
+        NFTAction::LockToken {
+            token_id: token_id.into(),
+            until,
+        },
+    )
+}
+
+pub fn unlock_token(nft: &Program, from: u64, token_id: u64) -> RunResult {
+    nft.send(
+        from,
+        NFTAction::UnlockToken {
+            token_id: token_id.into(),
+        },
+    )
+}
+
+pub fn is_locked(nft: &Program, from: u64, token_id: u64) -> RunResult {
+    nft.send(
+        from,
+        NFTAction::IsLocked {
+            token_id: token_id.into(),
+        },
+    )
+}
+
+pub fn update_metadata(
+    nft: &Program,
+    transaction_id: u64,
+    from: u64,
+    token_id: u64,
+    metadata: TokenMetadata,
+) -> RunResult {
+    nft.send(
+        from,
+        NFTAction::UpdateMetadata {
+            transaction_id,
+            token_id: token_id.into(),
+            metadata,
+        },
+    )
+}
+
+pub fn update_attributes(
+    nft: &Program,
+    transaction_id: u64,
+    from: u64,
+    token_id: u64,
+    attributes: Attributes,
+) -> RunResult {
+    nft.send(
+        from,
+        NFTAction::UpdateAttributes {
+            transaction_id,
+            token_id: token_id.into(),
+            attributes,
+        },
+    )
+}
+
+pub fn freeze_metadata(nft: &Program, from: u64, token_id: u64) -> RunResult {
+    nft.send(
+        from,
+        NFTAction::FreezeMetadata {
+            token_id: token_id.into(),
+        },
+    )
+}
+
+pub fn freeze_all_metadata(nft: &Program, from: u64) -> RunResult {
+    nft.send(from, NFTAction::FreezeAllMetadata)
+}
+
+pub fn set_base_uri(nft: &Program, from: u64, base_uri: &str) -> RunResult {
+    nft.send(
+        from,
+        NFTAction::SetBaseUri {
+            base_uri: base_uri.to_string(),
+        },
+    )
+}
+
+pub fn update_collection(nft: &Program, from: u64, update: CollectionUpdate) -> RunResult {
+    nft.send(from, NFTAction::UpdateCollection(update))
+}
+
+pub fn withdraw(nft: &Program, from: u64) -> RunResult {
+    nft.send(from, NFTAction::Withdraw)
+}
+
+pub fn claim_refund(nft: &Program, from: u64, value: u128) -> RunResult {
+    nft.send_with_value(from, NFTAction::ClaimRefund, value)
+}
+
+pub fn set_upcoming_phases(nft: &Program, from: u64, phases: Vec<MintPhase>) -> RunResult {
+    nft.send(from, NFTAction::SetUpcomingPhases { phases })
+}
+
+pub fn set_merkle_root(nft: &Program, from: u64, root: Option<H256>) -> RunResult {
+    nft.send(from, NFTAction::SetMerkleRoot { root })
+}
+
+pub fn redeem_voucher(
+    nft: &Program,
+    from: u64,
+    voucher: MintVoucher,
+    signature: [u8; 64],
+    value: u128,
+) -> RunResult {
+    nft.send_with_value(from, NFTAction::RedeemVoucher { voucher, signature }, value)
+}
+
 pub fn mint_to_actor(nft: &Program, transaction_id: u64, member: [u8; 32]) -> RunResult {
     nft.send(
         member,
         NFTAction::Mint {
             transaction_id,
-            token_metadata: TokenMetadata {
-                name: "CryptoKitty".to_string(),
-                description: "Description".to_string(),
-                media: "http://".to_string(),
-                reference: "http://".to_string(),
-            },
+            token_metadata: token_metadata(),
+            transferable: None,
+            attributes: Vec::new(),
+            allowlist_proof: None,
         },
     )
 }